@@ -1,21 +1,25 @@
 use std::{
+    fmt::{self, Debug, Formatter},
     io::{BufRead, BufReader, Read},
     marker::PhantomData,
+    sync::Arc,
     time::Duration,
 };
 
 use log::{debug, trace};
 use serde::de::DeserializeOwned;
 use serde_json::{from_str, json};
-use ureq::{Agent, Request};
 use url::Url;
 
 use crate::{
-    model::{Config, Connections, Delay, Log, Proxies, Proxy, Rules, Traffic, Version},
-    Error, Result,
+    model::{
+        Config, ConfigPatch, Connections, Delay, Level, Log, Provider, Providers, Proxies, Proxy,
+        Rules, Traffic, Version,
+    },
+    Backoff, Error, HttpRequest, HttpResponse, HttpTransport, Reconnect, Result, UreqTransport,
 };
 
-trait Convert<T: DeserializeOwned> {
+pub(crate) trait Convert<T: DeserializeOwned> {
     fn convert(self) -> Result<T>;
 }
 
@@ -25,11 +29,48 @@ impl<T: DeserializeOwned> Convert<T> for String {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Least timeout of [`Clash::healthcheck_provider`], which responds only
+/// after all proxies are tested
+pub const HEALTHCHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Endpoint of `/logs`, optionally filtered by `level` at server side
+pub(crate) fn log_endpoint(level: Option<Level>) -> String {
+    match level {
+        Some(level) => format!("logs?level={}", level.as_str()),
+        None => "logs".to_owned(),
+    }
+}
+
+/// Transport of longhaul requests, i.e. `/traffic`, `/logs` and
+/// `/connections`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LongHaulTransport {
+    /// HTTP stream with chunked-encoding
+    #[default]
+    Chunked,
+    /// WebSocket, with secret sent as query `token`. Requires feature
+    /// `websocket`
+    #[cfg(feature = "websocket")]
+    WebSocket,
+}
+
+#[derive(Clone)]
 pub struct ClashBuilder {
     url: Url,
     secret: Option<String>,
     timeout: Option<Duration>,
+    transport: LongHaulTransport,
+    http_transport: Option<Arc<dyn HttpTransport>>,
+}
+
+impl Debug for ClashBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClashBuilder")
+            .field("url", &self.url)
+            .field("timeout", &self.timeout)
+            .field("transport", &self.transport)
+            .finish_non_exhaustive()
+    }
 }
 
 impl ClashBuilder {
@@ -44,6 +85,8 @@ impl ClashBuilder {
             url,
             secret: None,
             timeout: None,
+            transport: LongHaulTransport::default(),
+            http_transport: None,
         })
     }
 
@@ -57,12 +100,33 @@ impl ClashBuilder {
         self
     }
 
+    pub fn transport(mut self, transport: LongHaulTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// HTTP stack to send requests with, [`UreqTransport`] if not set. Not
+    /// used by [`build_async`](ClashBuilder::build_async)
+    pub fn http_transport<T: HttpTransport + 'static>(mut self, http_transport: T) -> Self {
+        self.http_transport = Some(Arc::new(http_transport));
+        self
+    }
+
     pub fn build(self) -> Clash {
         let mut clash = Clash::new(self.url);
         clash.secret = self.secret;
         clash.timeout = self.timeout;
+        clash.transport = self.transport;
+        if let Some(http_transport) = self.http_transport {
+            clash.http_transport = http_transport;
+        }
         clash
     }
+
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> crate::AsyncClash {
+        crate::AsyncClash::with_options(self.url, self.secret, self.timeout)
+    }
 }
 
 /// # Clash API
@@ -70,12 +134,23 @@ impl ClashBuilder {
 /// Use struct `Clash` for interacting with Clash RESTful API.
 /// For more information, check <https://github.com/Dreamacro/clash/wiki/external-controller-API-reference###Proxies>,
 /// or maybe just read source code of clash
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Clash {
     url: Url,
     secret: Option<String>,
     timeout: Option<Duration>,
-    agent: Agent,
+    transport: LongHaulTransport,
+    http_transport: Arc<dyn HttpTransport>,
+}
+
+impl Debug for Clash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clash")
+            .field("url", &self.url)
+            .field("timeout", &self.timeout)
+            .field("transport", &self.transport)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Clash {
@@ -89,34 +164,47 @@ impl Clash {
             url,
             secret: None,
             timeout: None,
-            agent: Agent::new(),
+            transport: LongHaulTransport::default(),
+            http_transport: Arc::new(UreqTransport::default()),
         }
     }
 
-    fn build_request(&self, endpoint: &str, method: &str) -> Result<Request> {
-        let url = self.url.join(endpoint).map_err(|_| Error::url_parse())?;
-        let mut req = self.agent.request_url(method, &url);
-
-        if let Some(timeout) = self.timeout {
-            req = req.timeout(timeout)
-        }
-
-        if let Some(ref secret) = self.secret {
-            req = req.set("Authorization", &format!("Bearer {}", secret))
-        }
-
-        Ok(req)
+    /// Transport used by longhaul requests
+    pub fn transport(&self) -> LongHaulTransport {
+        self.transport
     }
 
-    fn build_request_without_timeout(&self, endpoint: &str, method: &str) -> Result<Request> {
+    fn build_request(
+        &self,
+        endpoint: &str,
+        method: &str,
+        body: Option<String>,
+        timeout: Option<Duration>,
+    ) -> Result<HttpRequest> {
         let url = self.url.join(endpoint).map_err(|_| Error::url_parse())?;
-        let mut req = self.agent.request_url(method, &url);
+        let headers = self
+            .secret
+            .iter()
+            .map(|secret| ("Authorization".to_owned(), format!("Bearer {}", secret)))
+            .collect();
+
+        Ok(HttpRequest {
+            method: method.to_owned(),
+            url,
+            headers,
+            body,
+            timeout,
+        })
+    }
 
-        if let Some(ref secret) = self.secret {
-            req = req.set("Authorization", &format!("Bearer {}", secret))
+    fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+        let mut resp = self.http_transport.send(req)?;
+        if resp.status >= 400 {
+            let mut body = String::new();
+            resp.body.read_to_string(&mut body).ok();
+            return Err(Error::from_response(resp.status, &body));
         }
-
-        Ok(req)
+        Ok(resp)
     }
 
     /// Send a oneshot request to the specific endpoint with method, with body
@@ -127,19 +215,12 @@ impl Clash {
         body: Option<String>,
     ) -> Result<String> {
         trace!("Body: {:#?}", body);
-        let resp = if let Some(body) = body {
-            self.build_request(endpoint, method)?.send_string(&body)?
-        } else {
-            self.build_request(endpoint, method)?.call()?
-        };
-
-        if resp.status() >= 400 {
-            return Err(Error::failed_response(resp.status()));
-        }
-
-        let text = resp
-            .into_string()
-            .map_err(|_| Error::bad_response_encoding())?;
+        let req = self.build_request(endpoint, method, body, self.timeout)?;
+        let mut text = String::new();
+        self.send(req)?
+            .body
+            .read_to_string(&mut text)
+            .map_err(Error::read_body)?;
         trace!("Received response: {}", text);
 
         Ok(text)
@@ -152,14 +233,16 @@ impl Clash {
     }
 
     /// Send a longhaul request to the specific endpoint with method,
-    /// Underlying is an http stream with chunked-encoding.
+    /// Underlying is an http stream with chunked-encoding, or a WebSocket if
+    /// [`LongHaulTransport::WebSocket`] is selected, in which case `method` is
+    /// ignored.
     ///
     /// Use [`LongHaul::next_item`], [`LongHaul::next_raw`] or
     /// [`Iterator::next`] to retreive data
     ///
     /// # Examplel
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// # use clashctl_core::{ Clash, model::Traffic }; use std::env;
     /// # fn main() {
     /// # let clash = Clash::builder(env::var("PROXY_ADDR").unwrap()).unwrap().build();
@@ -178,15 +261,28 @@ impl Clash {
         endpoint: &str,
         method: &str,
     ) -> Result<LongHaul<T>> {
-        let resp = self
-            .build_request_without_timeout(endpoint, method)?
-            .call()?;
-
-        if resp.status() >= 400 {
-            return Err(Error::failed_response(resp.status()));
+        #[cfg(feature = "websocket")]
+        if self.transport == LongHaulTransport::WebSocket {
+            let url = crate::ws_url(&self.url, endpoint, self.secret.as_deref())?;
+            return crate::ws_connect(url).map(LongHaul::from_websocket);
         }
 
-        Ok(LongHaul::new(Box::new(resp.into_reader())))
+        let req = self.build_request(endpoint, method, None, None)?;
+        Ok(LongHaul::new(self.send(req)?.body))
+    }
+
+    /// Same as [`longhaul_req`] with method `GET`, but reconnects with
+    /// `backoff` whenever the stream is lost. Connection is made lazily.
+    ///
+    /// See [`Reconnect`] for more information
+    ///
+    /// [`longhaul_req`]: Clash::longhaul_req
+    pub fn longhaul_reconnect<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        backoff: Backoff,
+    ) -> Reconnect<T> {
+        Reconnect::new(self.clone(), endpoint, backoff)
     }
 
     /// Helper function for method `GET`
@@ -205,6 +301,12 @@ impl Clash {
             .and_then(Convert::convert)
     }
 
+    /// Helper function for method `PATCH`
+    pub fn patch<T: DeserializeOwned>(&self, endpoint: &str, body: Option<String>) -> Result<T> {
+        self.oneshot_req_with_body(endpoint, "PATCH", body)
+            .and_then(Convert::convert)
+    }
+
     /// Get clash version
     pub fn get_version(&self) -> Result<Version> {
         self.get("version")
@@ -224,7 +326,19 @@ impl Clash {
     pub fn reload_configs(&self, force: bool, path: &str) -> Result<()> {
         let body = json!({ "path": path }).to_string();
         debug!("{}", body);
-        self.put::<String>(if force { "configs?force" } else { "configs" }, Some(body))
+        self.oneshot_req_with_body(
+            if force { "configs?force" } else { "configs" },
+            "PUT",
+            Some(body),
+        )
+        .map(|_| ())
+    }
+
+    /// Update part of base configs, fields left as `None` are not changed
+    pub fn patch_configs(&self, patch: &ConfigPatch) -> Result<()> {
+        let body = serde_json::to_string(patch)?;
+        debug!("{}", body);
+        self.oneshot_req_with_body("configs", "PATCH", Some(body))
             .map(|_| ())
     }
 
@@ -240,7 +354,7 @@ impl Clash {
 
     /// Get specific proxy information
     pub fn get_proxy(&self, proxy: &str) -> Result<Proxy> {
-        self.get(&format!("proxies/{}", proxy))
+        self.get(&format!("proxies/{}", urlencoding::encode(proxy)))
     }
 
     /// Get connections information
@@ -270,7 +384,7 @@ impl Clash {
         self.longhaul_req("traffic", "GET")
     }
 
-    /// Get real-time logs
+    /// Get real-time logs, at the log level of clash
     ///
     /// **Note**: This is a longhaul request, which will last forever until
     /// interrupted or disconnected.
@@ -279,7 +393,71 @@ impl Clash {
     ///
     /// [`longhaul_req`]: Clash::longhaul_req
     pub fn get_log(&self) -> Result<LongHaul<Log>> {
-        self.longhaul_req("logs", "GET")
+        self.longhaul_req(&log_endpoint(None), "GET")
+    }
+
+    /// Get real-time logs at or above `level`
+    ///
+    /// See [`get_log`] for more information
+    ///
+    /// [`get_log`]: Clash::get_log
+    pub fn get_log_with_level(&self, level: Level) -> Result<LongHaul<Log>> {
+        self.longhaul_req(&log_endpoint(Some(level)), "GET")
+    }
+
+    /// Get real-time traffic data, reconnecting when the stream is lost
+    ///
+    /// See [`longhaul_reconnect`] for more information
+    ///
+    /// [`longhaul_reconnect`]: Clash::longhaul_reconnect
+    pub fn get_traffic_reconnect(&self, backoff: Backoff) -> Reconnect<Traffic> {
+        self.longhaul_reconnect("traffic", backoff)
+    }
+
+    /// Get real-time logs, reconnecting when the stream is lost
+    ///
+    /// See [`longhaul_reconnect`] for more information
+    ///
+    /// [`longhaul_reconnect`]: Clash::longhaul_reconnect
+    pub fn get_log_reconnect(&self, level: Option<Level>, backoff: Backoff) -> Reconnect<Log> {
+        self.longhaul_reconnect(&log_endpoint(level), backoff)
+    }
+
+    /// Get real-time connections information, pushed by clash every second
+    ///
+    /// **Note**: Only available with [`LongHaulTransport::WebSocket`], since
+    /// `/connections` over plain HTTP returns a single snapshot. Use
+    /// [`get_connections`] otherwise.
+    ///
+    /// [`get_connections`]: Clash::get_connections
+    #[cfg(feature = "websocket")]
+    pub fn get_connections_stream(&self) -> Result<LongHaul<Connections>> {
+        self.ensure_websocket()?;
+        self.longhaul_req("connections", "GET")
+    }
+
+    /// Get real-time connections information, reconnecting when the stream is
+    /// lost
+    ///
+    /// See [`get_connections_stream`] and [`longhaul_reconnect`] for more
+    /// information
+    ///
+    /// [`get_connections_stream`]: Clash::get_connections_stream
+    /// [`longhaul_reconnect`]: Clash::longhaul_reconnect
+    #[cfg(feature = "websocket")]
+    pub fn get_connections_reconnect(&self, backoff: Backoff) -> Result<Reconnect<Connections>> {
+        self.ensure_websocket()?;
+        Ok(self.longhaul_reconnect("connections", backoff))
+    }
+
+    #[cfg(feature = "websocket")]
+    fn ensure_websocket(&self) -> Result<()> {
+        match self.transport {
+            LongHaulTransport::WebSocket => Ok(()),
+            _ => Err(Error::other(
+                "Streaming connections requires WebSocket transport".to_owned(),
+            )),
+        }
     }
 
     /// Get specific proxy delay test information
@@ -294,21 +472,68 @@ impl Clash {
 
     /// Select specific proxy
     pub fn set_proxygroup_selected(&self, group: &str, proxy: &str) -> Result<()> {
-        let body = format!("{{\"name\":\"{}\"}}", proxy);
-        self.oneshot_req_with_body(&format!("proxies/{}", group), "PUT", Some(body))?;
+        let body = json!({ "name": proxy }).to_string();
+        let endpoint = format!("proxies/{}", urlencoding::encode(group));
+        self.oneshot_req_with_body(&endpoint, "PUT", Some(body))?;
         Ok(())
     }
+
+    /// Get proxy providers information
+    pub fn get_providers(&self) -> Result<Providers> {
+        self.get("providers/proxies")
+    }
+
+    /// Get specific proxy provider information
+    pub fn get_provider(&self, provider: &str) -> Result<Provider> {
+        self.get(&format!(
+            "providers/proxies/{}",
+            urlencoding::encode(provider)
+        ))
+    }
+
+    /// Fetch proxies of specific provider again from its source
+    pub fn update_provider(&self, provider: &str) -> Result<()> {
+        let endpoint = format!("providers/proxies/{}", urlencoding::encode(provider));
+        self.oneshot_req(&endpoint, "PUT").map(|_| ())
+    }
+
+    /// Test delay of all proxies in specific provider. Clash responds after
+    /// all tests are done, so the timeout is raised to at least
+    /// [`HEALTHCHECK_TIMEOUT`]
+    pub fn healthcheck_provider(&self, provider: &str) -> Result<()> {
+        let endpoint = format!(
+            "providers/proxies/{}/healthcheck",
+            urlencoding::encode(provider)
+        );
+        let timeout = self.timeout.map(|x| x.max(HEALTHCHECK_TIMEOUT));
+        let req = self.build_request(&endpoint, "GET", None, timeout)?;
+        self.send(req).map(|_| ())
+    }
+}
+
+enum Source {
+    Chunked(BufReader<Box<dyn Read + Send>>),
+    #[cfg(feature = "websocket")]
+    WebSocket(Box<crate::WsStream>),
 }
 
 pub struct LongHaul<T: DeserializeOwned> {
-    reader: BufReader<Box<dyn Read + Send>>,
+    source: Source,
     ty: PhantomData<T>,
 }
 
 impl<T: DeserializeOwned> LongHaul<T> {
     pub fn new(reader: Box<dyn Read + Send>) -> Self {
         Self {
-            reader: BufReader::new(reader),
+            source: Source::Chunked(BufReader::new(reader)),
+            ty: PhantomData,
+        }
+    }
+
+    #[cfg(feature = "websocket")]
+    pub(crate) fn from_websocket(ws: crate::WsStream) -> Self {
+        Self {
+            source: Source::WebSocket(Box::new(ws)),
             ty: PhantomData,
         }
     }
@@ -318,11 +543,17 @@ impl<T: DeserializeOwned> LongHaul<T> {
     }
 
     pub fn next_raw(&mut self) -> Option<Result<String>> {
-        let mut buf = String::with_capacity(30);
-        match self.reader.read_line(&mut buf) {
-            Ok(0) => None,
-            Ok(_) => Some(Ok(buf)),
-            Err(e) => Some(Err(Error::other(format!("{:}", e)))),
+        match self.source {
+            Source::Chunked(ref mut reader) => {
+                let mut buf = String::with_capacity(30);
+                match reader.read_line(&mut buf) {
+                    Ok(0) => None,
+                    Ok(_) => Some(Ok(buf)),
+                    Err(e) => Some(Err(Error::other(format!("{:}", e)))),
+                }
+            }
+            #[cfg(feature = "websocket")]
+            Source::WebSocket(ref mut ws) => crate::ws_next_text(ws),
         }
     }
 }
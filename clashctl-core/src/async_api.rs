@@ -0,0 +1,417 @@
+use std::{
+    future::poll_fn,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use log::{debug, trace};
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use url::Url;
+
+use crate::{
+    api::{log_endpoint, Convert},
+    model::{
+        Config, ConfigPatch, Connections, Delay, Level, Log, Provider, Providers, Proxies, Proxy,
+        Rules, Traffic, Version,
+    },
+    ClashBuilder, Error, Result, HEALTHCHECK_TIMEOUT,
+};
+
+/// # Async Clash API
+///
+/// Async counterpart of [`Clash`](crate::Clash), built on `reqwest`. Requires
+/// feature `async`.
+///
+/// Methods share the same names and return the same [`model`](crate::model)
+/// types as [`Clash`](crate::Clash), so switching between the two only
+/// requires adding `.await`.
+#[derive(Debug, Clone)]
+pub struct AsyncClash {
+    url: Url,
+    secret: Option<String>,
+    timeout: Option<Duration>,
+    client: Client,
+}
+
+impl AsyncClash {
+    pub fn builder<S: Into<String>>(url: S) -> Result<ClashBuilder> {
+        ClashBuilder::new(url)
+    }
+
+    pub fn new(url: Url) -> Self {
+        Self::with_options(url, None, None)
+    }
+
+    pub(crate) fn with_options(
+        url: Url,
+        secret: Option<String>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        debug!("Url of clash RESTful API: {}", url);
+        Self {
+            url,
+            secret,
+            timeout,
+            client: Client::new(),
+        }
+    }
+
+    fn build_request(&self, endpoint: &str, method: &str) -> Result<RequestBuilder> {
+        let mut req = self.build_request_without_timeout(endpoint, method)?;
+
+        if let Some(timeout) = self.timeout {
+            req = req.timeout(timeout)
+        }
+
+        Ok(req)
+    }
+
+    fn build_request_without_timeout(
+        &self,
+        endpoint: &str,
+        method: &str,
+    ) -> Result<RequestBuilder> {
+        let url = self.url.join(endpoint).map_err(|_| Error::url_parse())?;
+        let method = Method::from_bytes(method.as_bytes())
+            .map_err(|_| Error::other(format!("Invalid method {}", method)))?;
+        let mut req = self.client.request(method, url);
+
+        if let Some(ref secret) = self.secret {
+            req = req.bearer_auth(secret)
+        }
+
+        Ok(req)
+    }
+
+    async fn send(req: RequestBuilder) -> Result<Response> {
+        let resp = req.send().await?;
+        let status = resp.status().as_u16();
+        if status >= 400 {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(Error::from_response(status, &body));
+        }
+        Ok(resp)
+    }
+
+    /// Send a oneshot request to the specific endpoint with method, with body
+    pub async fn oneshot_req_with_body(
+        &self,
+        endpoint: &str,
+        method: &str,
+        body: Option<String>,
+    ) -> Result<String> {
+        trace!("Body: {:#?}", body);
+        let mut req = self.build_request(endpoint, method)?;
+        if let Some(body) = body {
+            req = req.body(body)
+        }
+        let text = Self::send(req)
+            .await?
+            .text()
+            .await
+            .map_err(|_| Error::bad_response_encoding())?;
+        trace!("Received response: {}", text);
+
+        Ok(text)
+    }
+
+    /// Send a oneshot request to the specific endpoint with method, without
+    /// body
+    pub async fn oneshot_req(&self, endpoint: &str, method: &str) -> Result<String> {
+        self.oneshot_req_with_body(endpoint, method, None).await
+    }
+
+    /// Send a longhaul request to the specific endpoint with method,
+    /// Underlying is an http stream with chunked-encoding.
+    ///
+    /// Use [`AsyncLongHaul::next_item`], [`AsyncLongHaul::next_raw`] or
+    /// the [`Stream`] implementation to retreive data
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use clashctl_core::{ AsyncClash, model::Traffic }; use std::env;
+    /// # async fn run() {
+    /// # let clash = AsyncClash::builder(env::var("PROXY_ADDR").unwrap()).unwrap().build_async();
+    /// let mut traffics = clash
+    ///     .longhaul_req::<Traffic>("traffic", "GET")
+    ///     .await
+    ///     .expect("connect failed");
+    ///
+    /// while let Some(traffic) = traffics.next_item().await {
+    ///     println!("{:#?}", traffic)
+    /// }
+    /// # }
+    /// ```
+    pub async fn longhaul_req<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        method: &str,
+    ) -> Result<AsyncLongHaul<T>> {
+        let resp = Self::send(self.build_request_without_timeout(endpoint, method)?).await?;
+        Ok(AsyncLongHaul::new(Box::pin(resp.bytes_stream())))
+    }
+
+    /// Helper function for method `GET`
+    pub async fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        self.oneshot_req(endpoint, "GET")
+            .await
+            .and_then(Convert::convert)
+    }
+
+    /// Helper function for method `DELETE`
+    pub async fn delete(&self, endpoint: &str) -> Result<()> {
+        self.oneshot_req(endpoint, "DELETE").await.map(|_| ())
+    }
+
+    /// Helper function for method `PUT`
+    pub async fn put<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: Option<String>,
+    ) -> Result<T> {
+        self.oneshot_req_with_body(endpoint, "PUT", body)
+            .await
+            .and_then(Convert::convert)
+    }
+
+    /// Helper function for method `PATCH`
+    pub async fn patch<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: Option<String>,
+    ) -> Result<T> {
+        self.oneshot_req_with_body(endpoint, "PATCH", body)
+            .await
+            .and_then(Convert::convert)
+    }
+
+    /// Get clash version
+    pub async fn get_version(&self) -> Result<Version> {
+        self.get("version").await
+    }
+
+    /// Get base configs
+    pub async fn get_configs(&self) -> Result<Config> {
+        self.get("configs").await
+    }
+
+    /// Reloading base configs.
+    ///
+    /// - `force`: will change ports etc.,
+    /// - `path`: the absolute path to config file
+    ///
+    /// This will **NOT** affect `external-controller` & `secret`
+    pub async fn reload_configs(&self, force: bool, path: &str) -> Result<()> {
+        let body = json!({ "path": path }).to_string();
+        debug!("{}", body);
+        self.oneshot_req_with_body(
+            if force { "configs?force" } else { "configs" },
+            "PUT",
+            Some(body),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Update part of base configs, fields left as `None` are not changed
+    pub async fn patch_configs(&self, patch: &ConfigPatch) -> Result<()> {
+        let body = serde_json::to_string(patch)?;
+        debug!("{}", body);
+        self.oneshot_req_with_body("configs", "PATCH", Some(body))
+            .await
+            .map(|_| ())
+    }
+
+    /// Get proxies information
+    pub async fn get_proxies(&self) -> Result<Proxies> {
+        self.get("proxies").await
+    }
+
+    /// Get rules information
+    pub async fn get_rules(&self) -> Result<Rules> {
+        self.get("rules").await
+    }
+
+    /// Get specific proxy information
+    pub async fn get_proxy(&self, proxy: &str) -> Result<Proxy> {
+        self.get(&format!("proxies/{}", urlencoding::encode(proxy)))
+            .await
+    }
+
+    /// Get connections information
+    pub async fn get_connections(&self) -> Result<Connections> {
+        self.get("connections").await
+    }
+
+    /// Close all connections
+    pub async fn close_connections(&self) -> Result<()> {
+        self.delete("connections").await
+    }
+
+    /// Close specific connection
+    pub async fn close_one_connection(&self, id: &str) -> Result<()> {
+        self.delete(&format!("connections/{}", id)).await
+    }
+
+    /// Get real-time traffic data
+    ///
+    /// **Note**: This is a longhaul request, which will last forever until
+    /// interrupted or disconnected.
+    ///
+    /// See [`longhaul_req`] for more information
+    ///
+    /// [`longhaul_req`]: AsyncClash::longhaul_req
+    pub async fn get_traffic(&self) -> Result<AsyncLongHaul<Traffic>> {
+        self.longhaul_req("traffic", "GET").await
+    }
+
+    /// Get real-time logs, at the log level of clash
+    ///
+    /// **Note**: This is a longhaul request, which will last forever until
+    /// interrupted or disconnected.
+    ///
+    /// See [`longhaul_req`] for more information
+    ///
+    /// [`longhaul_req`]: AsyncClash::longhaul_req
+    pub async fn get_log(&self) -> Result<AsyncLongHaul<Log>> {
+        self.longhaul_req(&log_endpoint(None), "GET").await
+    }
+
+    /// Get real-time logs at or above `level`
+    ///
+    /// See [`get_log`] for more information
+    ///
+    /// [`get_log`]: AsyncClash::get_log
+    pub async fn get_log_with_level(&self, level: Level) -> Result<AsyncLongHaul<Log>> {
+        self.longhaul_req(&log_endpoint(Some(level)), "GET").await
+    }
+
+    /// Get specific proxy delay test information
+    pub async fn get_proxy_delay(
+        &self,
+        proxy: &str,
+        test_url: &str,
+        timeout: u64,
+    ) -> Result<Delay> {
+        use urlencoding::encode as e;
+        let (proxy, test_url) = (e(proxy), e(test_url));
+        self.get(&format!(
+            "proxies/{}/delay?url={}&timeout={}",
+            proxy, test_url, timeout
+        ))
+        .await
+    }
+
+    /// Select specific proxy
+    pub async fn set_proxygroup_selected(&self, group: &str, proxy: &str) -> Result<()> {
+        let body = json!({ "name": proxy }).to_string();
+        let endpoint = format!("proxies/{}", urlencoding::encode(group));
+        self.oneshot_req_with_body(&endpoint, "PUT", Some(body))
+            .await?;
+        Ok(())
+    }
+
+    /// Get proxy providers information
+    pub async fn get_providers(&self) -> Result<Providers> {
+        self.get("providers/proxies").await
+    }
+
+    /// Get specific proxy provider information
+    pub async fn get_provider(&self, provider: &str) -> Result<Provider> {
+        self.get(&format!(
+            "providers/proxies/{}",
+            urlencoding::encode(provider)
+        ))
+        .await
+    }
+
+    /// Fetch proxies of specific provider again from its source
+    pub async fn update_provider(&self, provider: &str) -> Result<()> {
+        let endpoint = format!("providers/proxies/{}", urlencoding::encode(provider));
+        self.oneshot_req(&endpoint, "PUT").await.map(|_| ())
+    }
+
+    /// Test delay of all proxies in specific provider. Clash responds after
+    /// all tests are done, so the timeout is raised to at least
+    /// [`HEALTHCHECK_TIMEOUT`]
+    pub async fn healthcheck_provider(&self, provider: &str) -> Result<()> {
+        let endpoint = format!(
+            "providers/proxies/{}/healthcheck",
+            urlencoding::encode(provider)
+        );
+        let mut req = self.build_request_without_timeout(&endpoint, "GET")?;
+        if let Some(timeout) = self.timeout {
+            req = req.timeout(timeout.max(HEALTHCHECK_TIMEOUT))
+        }
+        Self::send(req).await.map(|_| ())
+    }
+}
+
+type ByteStream = Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>;
+
+/// Async counterpart of [`LongHaul`](crate::LongHaul), yields one item per
+/// line of the underlying chunked stream.
+pub struct AsyncLongHaul<T: DeserializeOwned> {
+    stream: ByteStream,
+    buf: Vec<u8>,
+    ty: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> AsyncLongHaul<T> {
+    pub fn new(stream: ByteStream) -> Self {
+        Self {
+            stream,
+            buf: Vec::with_capacity(30),
+            ty: PhantomData,
+        }
+    }
+
+    pub async fn next_item(&mut self) -> Option<Result<T>> {
+        Some(self.next_raw().await?.and_then(Convert::convert))
+    }
+
+    pub async fn next_raw(&mut self) -> Option<Result<String>> {
+        poll_fn(|cx| self.poll_next_raw(cx)).await
+    }
+
+    fn poll_next_raw(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<String>>> {
+        loop {
+            if let Some(pos) = self.buf.iter().position(|x| *x == b'\n') {
+                let line = self.buf.drain(..=pos).collect();
+                return Poll::Ready(Some(Self::decode(line)));
+            }
+            match self.stream.as_mut().poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(bytes))) => self.buf.extend_from_slice(&bytes),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e.into()))),
+                // Flush whatever is left without a trailing newline
+                Poll::Ready(None) if !self.buf.is_empty() => {
+                    let line = std::mem::take(&mut self.buf);
+                    return Poll::Ready(Some(Self::decode(line)));
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn decode(line: Vec<u8>) -> Result<String> {
+        String::from_utf8(line).map_err(|_| Error::bad_response_encoding())
+    }
+}
+
+impl<T: DeserializeOwned> Stream for AsyncLongHaul<T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut()
+            .poll_next_raw(cx)
+            .map(|x| x.map(|raw| raw.and_then(Convert::convert)))
+    }
+}
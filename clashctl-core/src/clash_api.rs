@@ -0,0 +1,205 @@
+use serde::de::DeserializeOwned;
+
+use crate::{
+    api::log_endpoint,
+    model::{
+        Config, ConfigPatch, Connections, Delay, Level, Log, Provider, Providers, Proxies, Proxy,
+        Rules, Traffic, Version,
+    },
+    Backoff, Clash, LongHaul, LongHaulTransport, Reconnect, Result,
+};
+
+/// Endpoints of clash external controller, implemented by [`Clash`].
+///
+/// Code generic over this can run against a fake implementation in tests,
+/// e.g. `mock::FakeClash` with feature `mock`. Streaming endpoints are built
+/// on [`ClashApi::longhaul_req`], so implementations only need to provide that
+/// for them.
+pub trait ClashApi: Clone + Send + Sync {
+    /// Get clash version
+    fn get_version(&self) -> Result<Version>;
+
+    /// Get base configs
+    fn get_configs(&self) -> Result<Config>;
+
+    /// Reloading base configs, see [`Clash::reload_configs`]
+    fn reload_configs(&self, force: bool, path: &str) -> Result<()>;
+
+    /// Update part of base configs, fields left as `None` are not changed
+    fn patch_configs(&self, patch: &ConfigPatch) -> Result<()>;
+
+    /// Get proxies information
+    fn get_proxies(&self) -> Result<Proxies>;
+
+    /// Get specific proxy information
+    fn get_proxy(&self, proxy: &str) -> Result<Proxy>;
+
+    /// Get specific proxy delay test information
+    fn get_proxy_delay(&self, proxy: &str, test_url: &str, timeout: u64) -> Result<Delay>;
+
+    /// Select specific proxy
+    fn set_proxygroup_selected(&self, group: &str, proxy: &str) -> Result<()>;
+
+    /// Get rules information
+    fn get_rules(&self) -> Result<Rules>;
+
+    /// Get connections information
+    fn get_connections(&self) -> Result<Connections>;
+
+    /// Close all connections
+    fn close_connections(&self) -> Result<()>;
+
+    /// Close specific connection
+    fn close_one_connection(&self, id: &str) -> Result<()>;
+
+    /// Get proxy providers information
+    fn get_providers(&self) -> Result<Providers>;
+
+    /// Get specific proxy provider information
+    fn get_provider(&self, provider: &str) -> Result<Provider>;
+
+    /// Fetch proxies of specific provider again from its source
+    fn update_provider(&self, provider: &str) -> Result<()>;
+
+    /// Test delay of all proxies in specific provider, see
+    /// [`Clash::healthcheck_provider`]
+    fn healthcheck_provider(&self, provider: &str) -> Result<()>;
+
+    /// Start a longhaul request, see [`Clash::longhaul_req`]
+    fn longhaul_req<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        method: &str,
+    ) -> Result<LongHaul<T>>;
+
+    /// Transport used by longhaul requests
+    fn transport(&self) -> LongHaulTransport {
+        LongHaulTransport::default()
+    }
+
+    /// Get real-time traffic data, see [`Clash::get_traffic`]
+    fn get_traffic(&self) -> Result<LongHaul<Traffic>> {
+        self.longhaul_req("traffic", "GET")
+    }
+
+    /// Get real-time logs, see [`Clash::get_log`]
+    fn get_log(&self) -> Result<LongHaul<Log>> {
+        self.longhaul_req(&log_endpoint(None), "GET")
+    }
+
+    /// Get real-time logs at or above `level`, see
+    /// [`Clash::get_log_with_level`]
+    fn get_log_with_level(&self, level: Level) -> Result<LongHaul<Log>> {
+        self.longhaul_req(&log_endpoint(Some(level)), "GET")
+    }
+
+    /// Same as [`ClashApi::longhaul_req`] with method `GET`, but reconnects
+    /// with `backoff` whenever the stream is lost
+    fn longhaul_reconnect<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        backoff: Backoff,
+    ) -> Reconnect<T, Self> {
+        Reconnect::new(self.clone(), endpoint, backoff)
+    }
+
+    /// Get real-time traffic data, reconnecting when the stream is lost
+    fn get_traffic_reconnect(&self, backoff: Backoff) -> Reconnect<Traffic, Self> {
+        self.longhaul_reconnect("traffic", backoff)
+    }
+
+    /// Get real-time logs, reconnecting when the stream is lost
+    fn get_log_reconnect(&self, level: Option<Level>, backoff: Backoff) -> Reconnect<Log, Self> {
+        self.longhaul_reconnect(&log_endpoint(level), backoff)
+    }
+
+    /// Get real-time connections information, reconnecting when the stream is
+    /// lost. See [`Clash::get_connections_reconnect`]
+    #[cfg(feature = "websocket")]
+    fn get_connections_reconnect(&self, backoff: Backoff) -> Result<Reconnect<Connections, Self>> {
+        match self.transport() {
+            LongHaulTransport::WebSocket => Ok(self.longhaul_reconnect("connections", backoff)),
+            _ => Err(crate::Error::other(
+                "Streaming connections requires WebSocket transport".to_owned(),
+            )),
+        }
+    }
+}
+
+impl ClashApi for Clash {
+    fn get_version(&self) -> Result<Version> {
+        Clash::get_version(self)
+    }
+
+    fn get_configs(&self) -> Result<Config> {
+        Clash::get_configs(self)
+    }
+
+    fn reload_configs(&self, force: bool, path: &str) -> Result<()> {
+        Clash::reload_configs(self, force, path)
+    }
+
+    fn patch_configs(&self, patch: &ConfigPatch) -> Result<()> {
+        Clash::patch_configs(self, patch)
+    }
+
+    fn get_proxies(&self) -> Result<Proxies> {
+        Clash::get_proxies(self)
+    }
+
+    fn get_proxy(&self, proxy: &str) -> Result<Proxy> {
+        Clash::get_proxy(self, proxy)
+    }
+
+    fn get_proxy_delay(&self, proxy: &str, test_url: &str, timeout: u64) -> Result<Delay> {
+        Clash::get_proxy_delay(self, proxy, test_url, timeout)
+    }
+
+    fn set_proxygroup_selected(&self, group: &str, proxy: &str) -> Result<()> {
+        Clash::set_proxygroup_selected(self, group, proxy)
+    }
+
+    fn get_rules(&self) -> Result<Rules> {
+        Clash::get_rules(self)
+    }
+
+    fn get_connections(&self) -> Result<Connections> {
+        Clash::get_connections(self)
+    }
+
+    fn close_connections(&self) -> Result<()> {
+        Clash::close_connections(self)
+    }
+
+    fn close_one_connection(&self, id: &str) -> Result<()> {
+        Clash::close_one_connection(self, id)
+    }
+
+    fn get_providers(&self) -> Result<Providers> {
+        Clash::get_providers(self)
+    }
+
+    fn get_provider(&self, provider: &str) -> Result<Provider> {
+        Clash::get_provider(self, provider)
+    }
+
+    fn update_provider(&self, provider: &str) -> Result<()> {
+        Clash::update_provider(self, provider)
+    }
+
+    fn healthcheck_provider(&self, provider: &str) -> Result<()> {
+        Clash::healthcheck_provider(self, provider)
+    }
+
+    fn longhaul_req<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        method: &str,
+    ) -> Result<LongHaul<T>> {
+        Clash::longhaul_req(self, endpoint, method)
+    }
+
+    fn transport(&self) -> LongHaulTransport {
+        Clash::transport(self)
+    }
+}
@@ -1,10 +1,22 @@
+use std::io;
+
+use serde::Deserialize;
+
 #[derive(thiserror::Error, Debug)]
 pub enum ErrorKind {
     #[error("Invalid URL format")]
     UrlParseError,
 
     #[error("Error while requesting API ({0})")]
-    RequestError(#[from] ureq::Error),
+    RequestError(ureq::Error),
+
+    #[cfg(feature = "async")]
+    #[error("Error while requesting API ({0})")]
+    AsyncRequestError(reqwest::Error),
+
+    #[cfg(feature = "websocket")]
+    #[error("Error while requesting API ({0})")]
+    WebSocketError(tungstenite::Error),
 
     #[error("Broken response from server")]
     BadResponseEncoding,
@@ -12,13 +24,38 @@ pub enum ErrorKind {
     #[error("Broken response from server ({0})")]
     BadResponseFormat(#[from] serde_json::Error),
 
-    #[error("Failed response from server (Code {0})")]
-    FailedResponse(u16),
+    #[error("Unauthorized, secret is missing or wrong{}", suffix(.0))]
+    Unauthorized(Option<String>),
+
+    #[error("Not found{}", suffix(.0))]
+    NotFound(Option<String>),
+
+    #[error("Bad request{}", suffix(.0))]
+    BadRequest(Option<String>),
+
+    #[error("Failed response from server (Code {0}){}", suffix(.1))]
+    FailedResponse(u16, Option<String>),
+
+    #[error("Request timed out")]
+    Timeout,
+
+    #[error("Cannot connect to server ({0})")]
+    ConnectionRefused(String),
 
     #[error("Other errors ({0})")]
     Other(String),
 }
 
+fn suffix(msg: &Option<String>) -> String {
+    msg.as_ref().map(|x| format!(": {}", x)).unwrap_or_default()
+}
+
+/// Error body of clash, e.g. `{"message":"Resource not found"}`
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error(transparent)]
 pub struct Error(Box<ErrorKind>);
@@ -28,17 +65,56 @@ impl Error {
         Error(Box::new(ErrorKind::UrlParseError))
     }
 
+    /// Failed response without body, see [`Error::from_response`]
     pub fn failed_response(status: u16) -> Self {
-        Error(Box::new(ErrorKind::FailedResponse(status)))
+        Self::from_response(status, "")
+    }
+
+    /// Failed response with status and body. Message in body, either clash's
+    /// `{"message":...}` or plain text, is kept in the error
+    pub fn from_response(status: u16, body: &str) -> Self {
+        let body = body.trim();
+        let msg = match serde_json::from_str::<ErrorBody>(body) {
+            Ok(ErrorBody { message }) => Some(message),
+            Err(_) if body.is_empty() => None,
+            Err(_) => Some(body.to_owned()),
+        };
+        Error(Box::new(match status {
+            400 => ErrorKind::BadRequest(msg),
+            401 | 403 => ErrorKind::Unauthorized(msg),
+            404 => ErrorKind::NotFound(msg),
+            _ => ErrorKind::FailedResponse(status, msg),
+        }))
     }
 
     pub fn bad_response_encoding() -> Self {
         Error(Box::new(ErrorKind::BadResponseEncoding))
     }
 
+    pub fn timeout() -> Self {
+        Error(Box::new(ErrorKind::Timeout))
+    }
+
+    pub fn connection_refused(msg: String) -> Self {
+        Error(Box::new(ErrorKind::ConnectionRefused(msg)))
+    }
+
     pub fn other(msg: String) -> Self {
         Error(Box::new(ErrorKind::Other(msg)))
     }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0
+    }
+
+    /// Error reading response body, timeouts are reported as
+    /// [`ErrorKind::Timeout`]
+    pub(crate) fn read_body(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => Self::timeout(),
+            _ => Self::bad_response_encoding(),
+        }
+    }
 }
 
 impl<E> From<E> for Error
@@ -50,4 +126,79 @@ where
     }
 }
 
+impl From<ureq::Error> for ErrorKind {
+    fn from(err: ureq::Error) -> Self {
+        let timed_out = std::error::Error::source(&err)
+            .and_then(|x| x.downcast_ref::<io::Error>())
+            .is_some_and(|x| {
+                matches!(
+                    x.kind(),
+                    io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                )
+            });
+        match err {
+            ureq::Error::Status(status, resp) => {
+                let body = resp.into_string().unwrap_or_default();
+                *Error::from_response(status, &body).0
+            }
+            _ if timed_out => ErrorKind::Timeout,
+            ureq::Error::Transport(ref t) if t.kind() == ureq::ErrorKind::ConnectionFailed => {
+                ErrorKind::ConnectionRefused(t.to_string())
+            }
+            _ => ErrorKind::RequestError(err),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<reqwest::Error> for ErrorKind {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            ErrorKind::Timeout
+        } else if err.is_connect() {
+            ErrorKind::ConnectionRefused(err.to_string())
+        } else {
+            ErrorKind::AsyncRequestError(err)
+        }
+    }
+}
+
+#[cfg(feature = "websocket")]
+impl From<tungstenite::Error> for ErrorKind {
+    fn from(err: tungstenite::Error) -> Self {
+        match err {
+            tungstenite::Error::Io(ref e) => match e.kind() {
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => ErrorKind::Timeout,
+                io::ErrorKind::ConnectionRefused => ErrorKind::ConnectionRefused(e.to_string()),
+                _ => ErrorKind::WebSocketError(err),
+            },
+            _ => ErrorKind::WebSocketError(err),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[test]
+fn test_from_response() {
+    let kind = |status, body| Error::from_response(status, body).0;
+
+    assert!(matches!(
+        *kind(401, r#"{"message":"Unauthorized"}"#),
+        ErrorKind::Unauthorized(Some(ref x)) if x == "Unauthorized"
+    ));
+    assert!(matches!(*kind(403, ""), ErrorKind::Unauthorized(None)));
+    assert!(matches!(
+        *kind(404, "no such proxy\n"),
+        ErrorKind::NotFound(Some(ref x)) if x == "no such proxy"
+    ));
+    assert!(matches!(*kind(400, "{}"), ErrorKind::BadRequest(Some(_))));
+    assert!(matches!(
+        *kind(503, ""),
+        ErrorKind::FailedResponse(503, None)
+    ));
+    assert_eq!(
+        kind(404, r#"{"message":"Resource not found"}"#).to_string(),
+        "Not found: Resource not found"
+    );
+}
@@ -1,10 +1,25 @@
 #![doc = include_str!("../README.md")]
 
-mod_use::mod_use![api, error];
+mod_use::mod_use![api, clash_api, error, matcher, reconnect, tracker, transport];
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "async")] {
+        mod_use::mod_use![async_api];
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "websocket")] {
+        mod_use::mod_use![websocket];
+    }
+}
 
 #[cfg(test)]
 mod test;
 
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+
 pub mod model;
 
 #[cfg(feature = "enum_ext")]
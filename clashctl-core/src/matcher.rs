@@ -0,0 +1,243 @@
+use std::net::{IpAddr, SocketAddr};
+
+use serde::Serialize;
+use url::Url;
+
+use crate::model::{Rule, RuleType, Rules};
+
+/// What to look up in rules, like metadata of a connection.
+///
+/// Source address and port never match unless given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MatchTarget {
+    pub host: Option<String>,
+    pub dst_ip: Option<IpAddr>,
+    pub dst_port: Option<u16>,
+    pub src_ip: Option<IpAddr>,
+    pub src_port: Option<u16>,
+    pub process: Option<String>,
+}
+
+impl MatchTarget {
+    /// Parse a domain or an IP, with optional port, e.g. `google.com`,
+    /// `1.1.1.1:53`, `[::1]:80` or `https://github.com/rust-lang`
+    pub fn parse(target: &str) -> Self {
+        let target = target.trim();
+        if target.contains("://") {
+            if let Ok(url) = Url::parse(target) {
+                let mut ret = Self::parse(url.host_str().unwrap_or_default());
+                ret.dst_port = url.port_or_known_default();
+                return ret;
+            }
+        }
+        if let Ok(addr) = target.parse::<SocketAddr>() {
+            return Self {
+                dst_ip: Some(addr.ip()),
+                dst_port: Some(addr.port()),
+                ..Self::default()
+            };
+        }
+        let ip = target.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = ip.parse::<IpAddr>() {
+            return Self {
+                dst_ip: Some(ip),
+                ..Self::default()
+            };
+        }
+        let (host, port) = match target.rsplit_once(':') {
+            Some((host, port)) => match port.parse() {
+                Ok(port) => (host, Some(port)),
+                Err(_) => (target, None),
+            },
+            None => (target, None),
+        };
+        Self {
+            host: Some(host.to_lowercase()),
+            dst_port: port,
+            ..Self::default()
+        }
+    }
+
+    /// Whether `rule` matches this target, or `Err` with the reason if it
+    /// cannot be evaluated locally
+    pub fn check(&self, rule: &Rule) -> Result<bool, &'static str> {
+        let host = self.host.as_deref();
+        let payload = rule.payload.to_lowercase();
+        match rule.rule_type {
+            RuleType::Domain => Ok(host == Some(payload.as_str())),
+            RuleType::DomainSuffix => Ok(host.is_some_and(|host| {
+                host == payload
+                    || host
+                        .strip_suffix(payload.as_str())
+                        .is_some_and(|x| x.ends_with('.'))
+            })),
+            RuleType::DomainKeyword => Ok(host.is_some_and(|host| host.contains(&payload))),
+            RuleType::IPCIDR => match self.dst_ip {
+                Some(ip) => Ok(cidr_contains(&payload, ip)),
+                None if host.is_some() => Err("requires DNS resolution"),
+                None => Ok(false),
+            },
+            RuleType::SrcIPCIDR => Ok(self.src_ip.is_some_and(|ip| cidr_contains(&payload, ip))),
+            RuleType::DstPort => port_matches(&payload, self.dst_port),
+            RuleType::SrcPort => port_matches(&payload, self.src_port),
+            RuleType::Process => match self.process {
+                Some(ref process) => Ok(process.to_lowercase() == payload),
+                None => Err("process is not given"),
+            },
+            RuleType::GeoIP => Err("requires GeoIP database"),
+            RuleType::Match => Ok(true),
+            RuleType::Direct | RuleType::Reject | RuleType::Unknown => Err("unsupported rule type"),
+        }
+    }
+}
+
+/// Whether `port` equals `payload`. Unknown port can neither match nor miss
+fn port_matches(payload: &str, port: Option<u16>) -> Result<bool, &'static str> {
+    match port {
+        Some(port) => Ok(payload == port.to_string()),
+        None => Err("port is not given"),
+    }
+}
+
+/// Whether `ip` is in `cidr`, e.g. `192.168.0.0/16`. Invalid CIDR never
+/// matches
+fn cidr_contains(cidr: &str, ip: IpAddr) -> bool {
+    let (net, len) = match cidr.split_once('/') {
+        Some((net, len)) => (net, len.parse::<u32>().ok()),
+        None => (cidr, None),
+    };
+    match (net.parse::<IpAddr>(), ip) {
+        (Ok(IpAddr::V4(net)), IpAddr::V4(ip)) => {
+            let len = len.unwrap_or(32).min(32);
+            let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+            u32::from(net) & mask == u32::from(ip) & mask
+        }
+        (Ok(IpAddr::V6(net)), IpAddr::V6(ip)) => {
+            let len = len.unwrap_or(128).min(128);
+            let mask = u128::MAX.checked_shl(128 - len).unwrap_or(0);
+            u128::from(net) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedRule<'a> {
+    pub index: usize,
+    #[serde(flatten)]
+    pub rule: &'a Rule,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct UnknownRule<'a> {
+    #[serde(flatten)]
+    pub rule: IndexedRule<'a>,
+    pub reason: &'static str,
+}
+
+/// Result of [`Rules::find_match`]
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleMatch<'a> {
+    /// First rule that matches
+    pub matched: Option<IndexedRule<'a>>,
+    /// Rules before `matched` that cannot be evaluated locally, any of which
+    /// may match instead
+    pub unknown: Vec<UnknownRule<'a>>,
+}
+
+impl Rules {
+    /// Walk through rules in order to find the first one matching `target`
+    pub fn find_match(&self, target: &MatchTarget) -> RuleMatch<'_> {
+        let mut ret = RuleMatch::default();
+        for (index, rule) in self.rules.iter().enumerate() {
+            match target.check(rule) {
+                Ok(true) => {
+                    ret.matched = Some(IndexedRule { index, rule });
+                    break;
+                }
+                Ok(false) => {}
+                Err(reason) => ret.unknown.push(UnknownRule {
+                    rule: IndexedRule { index, rule },
+                    reason,
+                }),
+            }
+        }
+        ret
+    }
+}
+
+#[test]
+fn test_match_target() {
+    let target = MatchTarget::parse("WWW.Google.com:443");
+    assert_eq!(target.host.as_deref(), Some("www.google.com"));
+    assert_eq!(target.dst_port, Some(443));
+
+    let target = MatchTarget::parse("https://github.com/rust-lang");
+    assert_eq!(target.host.as_deref(), Some("github.com"));
+    assert_eq!(target.dst_port, Some(443));
+
+    let target = MatchTarget::parse("[::1]:53");
+    assert_eq!(target.dst_ip, Some("::1".parse().unwrap()));
+    assert_eq!(target.dst_port, Some(53));
+    assert_eq!(
+        MatchTarget::parse("10.0.0.1").dst_ip,
+        Some("10.0.0.1".parse().unwrap())
+    );
+
+    assert!(cidr_contains("10.0.0.0/8", "10.1.2.3".parse().unwrap()));
+    assert!(!cidr_contains("10.0.0.0/8", "11.0.0.1".parse().unwrap()));
+    assert!(cidr_contains("0.0.0.0/0", "1.1.1.1".parse().unwrap()));
+    assert!(cidr_contains("fd00::/8", "fd12::1".parse().unwrap()));
+    assert!(!cidr_contains("fd00::/8", "10.0.0.1".parse().unwrap()));
+}
+
+#[test]
+fn test_find_match() {
+    let rule = |rule_type, payload: &str, proxy: &str| Rule {
+        rule_type,
+        payload: payload.to_owned(),
+        proxy: proxy.to_owned(),
+    };
+    let rules = Rules {
+        rules: vec![
+            rule(RuleType::DomainSuffix, "google.com", "Proxy"),
+            rule(RuleType::DomainKeyword, "baidu", "DIRECT"),
+            rule(RuleType::IPCIDR, "192.168.0.0/16", "DIRECT"),
+            rule(RuleType::GeoIP, "CN", "DIRECT"),
+            rule(RuleType::DstPort, "22", "DIRECT"),
+            rule(RuleType::Match, "", "Proxy"),
+        ],
+    };
+
+    let result = rules.find_match(&MatchTarget::parse("mail.google.com"));
+    assert_eq!(result.matched.map(|x| x.index), Some(0));
+    assert!(result.unknown.is_empty());
+
+    // Not a subdomain
+    let result = rules.find_match(&MatchTarget::parse("notgoogle.com"));
+    assert_eq!(result.matched.map(|x| x.index), Some(5));
+    assert_eq!(
+        result
+            .unknown
+            .iter()
+            .map(|x| (x.rule.index, x.reason))
+            .collect::<Vec<_>>(),
+        vec![
+            (2, "requires DNS resolution"),
+            (3, "requires GeoIP database"),
+            (4, "port is not given")
+        ]
+    );
+
+    let result = rules.find_match(&MatchTarget::parse("192.168.1.1:22"));
+    assert_eq!(result.matched.map(|x| x.index), Some(2));
+
+    let result = rules.find_match(&MatchTarget::parse("10.0.0.1:22"));
+    assert_eq!(result.matched.map(|x| x.index), Some(4));
+
+    let port = rule(RuleType::SrcPort, "22", "DIRECT");
+    assert_eq!(
+        MatchTarget::parse("10.0.0.1:22").check(&port),
+        Err("port is not given")
+    );
+}
@@ -0,0 +1,895 @@
+//! In-process mock of clash external controller, for tests.
+//!
+//! [`MockClash`] listens on a random local port and serves `/version`,
+//! `/configs`, `/proxies`, `/providers/proxies`, `/rules`, `/connections`,
+//! `/traffic` and `/logs` from [`MockState`], which can be changed at any time.
+//! Requests that change things, like `PATCH /configs` or `DELETE /connections`,
+//! update the state as clash would. Any endpoint can be overridden with a
+//! [`MockResponse`] to simulate errors, slow responses or broken streams.
+//!
+//! Unlike clash, streams of `/traffic` and `/logs` end after sending all
+//! items in the state.
+//!
+//! ```rust
+//! # use clashctl_core::mock::{MockClash, MockResponse};
+//! let mock = MockClash::start();
+//! let clash = mock.clash();
+//! assert!(clash.get_proxies().is_ok());
+//!
+//! mock.respond("GET", "/proxies", MockResponse::status(500));
+//! assert!(clash.get_proxies().is_err());
+//! ```
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Cursor, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{sleep, spawn},
+    time::Duration,
+};
+
+use log::{debug, trace};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{from_value, json, to_value, Value};
+
+use crate::{
+    model::{
+        Config, ConfigPatch, Connections, Delay, Level, Log, Provider, Providers, Proxies, Proxy,
+        Rules, Traffic, Version,
+    },
+    Clash, ClashApi, Error, LongHaul,
+};
+
+/// Data served by [`MockClash`]
+#[derive(Debug, Clone)]
+pub struct MockState {
+    pub version: Version,
+    pub configs: Config,
+    pub proxies: Proxies,
+    pub providers: Providers,
+    pub rules: Rules,
+    pub connections: Connections,
+    /// Sent by `/traffic`, one per line
+    pub traffic: Vec<Traffic>,
+    /// Sent by `/logs`, filtered by `level` in query
+    pub logs: Vec<Log>,
+    /// Result of `/proxies/:name/delay`, in ms. Normal proxies not listed
+    /// here have a delay of 100ms
+    pub delays: HashMap<String, u64>,
+    /// Interval between items of `/traffic` and `/logs`
+    pub interval: Duration,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        let history = json!([{ "time": "2022-01-01T00:00:00Z", "delay": 100 }]);
+        let proxy = |kind: &str| json!({ "type": kind, "history": history, "udp": true });
+        let group = |kind: &str, all: &[&str]| {
+            json!({
+                "type": kind,
+                "history": [],
+                "all": all,
+                "now": all[0]
+            })
+        };
+        let member = |name: &str, kind: &str| {
+            let mut proxy = proxy(kind);
+            proxy["name"] = json!(name);
+            proxy
+        };
+        let connection = |id: &str, host: &str, rule: &str, payload: &str, chains: &[&str]| {
+            json!({
+                "id": id,
+                "upload": 1024,
+                "download": 4096,
+                "metadata": {
+                    "type": "HTTP",
+                    "sourceIP": "127.0.0.1",
+                    "sourcePort": "50000",
+                    "destinationIP": "",
+                    "destinationPort": "443",
+                    "host": host,
+                    "network": "tcp"
+                },
+                "rule": rule,
+                "rulePayload": payload,
+                "start": "2022-01-01T00:00:00Z",
+                "chains": chains
+            })
+        };
+        let log = |kind: &str, payload: &str| json!({ "type": kind, "payload": payload });
+
+        Self {
+            version: fixture(json!({ "version": "1.10.0" })),
+            configs: fixture(json!({
+                "port": 7890,
+                "socks-port": 7891,
+                "redir-port": 0,
+                "tproxy-port": 0,
+                "mixed-port": 0,
+                "allow-lan": false,
+                "ipv6": false,
+                "mode": "rule",
+                "log-level": "info",
+                "bind-address": "*",
+                "authentication": []
+            })),
+            proxies: fixture(json!({ "proxies": {
+                "DIRECT": { "type": "Direct", "history": [], "udp": true },
+                "REJECT": { "type": "Reject", "history": [], "udp": true },
+                "HK-01": proxy("Shadowsocks"),
+                "US-01": proxy("Vmess"),
+                "Proxy": group("Selector", &["HK-01", "US-01"]),
+                "Auto": group("URLTest", &["HK-01", "US-01"]),
+                "GLOBAL": group("Selector", &["DIRECT", "Proxy", "Auto"]),
+            }})),
+            providers: fixture(json!({ "providers": {
+                "default": {
+                    "name": "default",
+                    "type": "Proxy",
+                    "vehicleType": "Compatible",
+                    "proxies": [member("DIRECT", "Direct"), member("REJECT", "Reject")]
+                },
+                "Subscription": {
+                    "name": "Subscription",
+                    "type": "Proxy",
+                    "vehicleType": "HTTP",
+                    "proxies": [member("HK-01", "Shadowsocks"), member("US-01", "Vmess")],
+                    "updatedAt": "2022-01-01T00:00:00Z",
+                    "subscriptionInfo": {
+                        "Upload": 1_073_741_824_u64,
+                        "Download": 10_737_418_240_u64,
+                        "Total": 107_374_182_400_u64,
+                        "Expire": 1_893_456_000
+                    }
+                },
+            }})),
+            rules: fixture(json!({ "rules": [
+                { "type": "DomainSuffix", "payload": "google.com", "proxy": "Proxy" },
+                { "type": "DomainKeyword", "payload": "github", "proxy": "Auto" },
+                { "type": "GeoIP", "payload": "CN", "proxy": "DIRECT" },
+                { "type": "Match", "payload": "", "proxy": "Proxy" },
+            ]})),
+            connections: fixture(json!({
+                "connections": [
+                    connection(
+                        "1", "www.google.com", "DomainSuffix", "google.com", &["HK-01", "Proxy"]
+                    ),
+                    connection("2", "github.com", "DomainKeyword", "github", &["US-01", "Auto"]),
+                ],
+                "downloadTotal": 8192,
+                "uploadTotal": 2048
+            })),
+            traffic: fixture(json!([
+                { "up": 0, "down": 0 },
+                { "up": 1024, "down": 4096 },
+                { "up": 512, "down": 2048 },
+            ])),
+            logs: fixture(json!([
+                log(
+                    "info",
+                    "[TCP] 127.0.0.1:50000 --> www.google.com:443 match DomainSuffix(google.com) \
+                     using Proxy[HK-01]"
+                ),
+                log(
+                    "warning",
+                    "[TCP] dial Proxy (match DomainSuffix/google.com) to www.google.com:443 \
+                     error: i/o timeout"
+                ),
+                log("debug", "[DNS] www.google.com --> 142.250.1.1"),
+                log(
+                    "error",
+                    "Start Mixed(http+socks) server error: address already in use"
+                ),
+            ])),
+            delays: HashMap::new(),
+            interval: Duration::ZERO,
+        }
+    }
+}
+
+/// Fixtures are written in JSON so that they work with or without feature
+/// `deserialize`
+fn fixture<T: DeserializeOwned>(value: Value) -> T {
+    from_value(value).expect("Default fixtures should be valid")
+}
+
+// Behaviors shared by `MockClash` and `FakeClash`, failing with the status
+// clash would respond with
+impl MockState {
+    fn patch_configs(&mut self, patch: Value) -> Result<(), u16> {
+        let Value::Object(patch) = patch else {
+            return Err(400);
+        };
+        let mut configs = to_value(&self.configs).expect("Configs should be serializable");
+        configs
+            .as_object_mut()
+            .expect("Configs should be an object")
+            .extend(patch);
+        self.configs = from_value(configs).map_err(|_| 400_u16)?;
+        Ok(())
+    }
+
+    fn select(&mut self, group: &str, proxy: Option<&str>) -> Result<(), u16> {
+        let group = self.proxies.proxies.get_mut(group).ok_or(404_u16)?;
+        match proxy {
+            Some(proxy)
+                if group.proxy_type.is_selector()
+                    && group.all.iter().flatten().any(|x| x == proxy) =>
+            {
+                group.now = Some(proxy.to_owned());
+                Ok(())
+            }
+            _ => Err(400),
+        }
+    }
+
+    fn delay(&self, proxy: &str) -> Result<u64, u16> {
+        match self.proxies.get(proxy) {
+            None => Err(404),
+            Some(x) if x.proxy_type.is_normal() => {
+                Ok(self.delays.get(proxy).copied().unwrap_or(100))
+            }
+            Some(_) => Err(503),
+        }
+    }
+
+    /// Close connection with `id`, or all connections if it's `None`. Like
+    /// clash, unknown ids are ignored
+    fn close_connections(&mut self, id: Option<&str>) -> Result<(), u16> {
+        match id {
+            Some(id) => self.connections.connections.retain(|x| x.id != id),
+            None => self.connections.connections.clear(),
+        }
+        Ok(())
+    }
+
+    /// Update or healthcheck of a provider, which only checks that it exists
+    fn provider(&self, name: &str) -> Result<(), u16> {
+        match self.providers.get(name) {
+            Some(_) => Ok(()),
+            None => Err(404),
+        }
+    }
+
+    /// Lines sent by `/traffic` or `/logs`, the latter filtered by `level`
+    fn stream(&self, endpoint: &str, level: Option<&str>) -> Result<Vec<String>, u16> {
+        let lines = |items: Vec<Value>| items.iter().map(|x| x.to_string() + "\n").collect();
+        match endpoint {
+            "traffic" => Ok(lines(self.traffic.iter().map(|x| json!(x)).collect())),
+            "logs" => {
+                let level = match level {
+                    Some(level) => from_value::<Level>(json!(level)).map_err(|_| 400_u16)?,
+                    None => Level::Debug,
+                };
+                Ok(lines(
+                    self.logs
+                        .iter()
+                        .filter(|x| x.log_type <= level)
+                        .map(|x| json!(x))
+                        .collect(),
+                ))
+            }
+            _ => Err(404),
+        }
+    }
+}
+
+/// Response used in place of the default behavior of an endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockResponse {
+    status: u16,
+    body: MockBody,
+    delay: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MockBody {
+    Text(String),
+    Chunks {
+        chunks: Vec<String>,
+        interval: Duration,
+    },
+}
+
+impl MockResponse {
+    /// `200 OK` with `value` as JSON body
+    pub fn json<T: Serialize>(value: &T) -> Self {
+        Self::text(serde_json::to_string(value).expect("Value should be serializable"))
+    }
+
+    /// `200 OK` with raw body, e.g. broken JSON
+    pub fn text<S: Into<String>>(body: S) -> Self {
+        Self {
+            status: 200,
+            body: MockBody::Text(body.into()),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Error response with body like clash's, e.g. `{"message":"Not Found"}`
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            ..Self::text(json!({ "message": reason(status) }).to_string())
+        }
+    }
+
+    /// Chunked stream of `items` as JSON, one per line
+    pub fn stream<T: Serialize>(items: &[T]) -> Self {
+        Self::chunks(
+            items
+                .iter()
+                .map(|x| serde_json::to_string(x).expect("Item should be serializable") + "\n")
+                .collect(),
+        )
+    }
+
+    /// Chunked stream with each of `chunks` sent as is, so lines can be split
+    /// across chunks
+    pub fn chunks(chunks: Vec<String>) -> Self {
+        Self {
+            status: 200,
+            body: MockBody::Chunks {
+                chunks,
+                interval: Duration::ZERO,
+            },
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Wait before responding
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Wait between chunks of a stream
+    pub fn interval(mut self, interval: Duration) -> Self {
+        if let MockBody::Chunks {
+            interval: ref mut x,
+            ..
+        } = self.body
+        {
+            *x = interval
+        }
+        self
+    }
+}
+
+/// Request received by [`MockClash`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockRequest {
+    pub method: String,
+    /// Path without query, percent-decoded, e.g. `/proxies/HK-01`
+    pub path: String,
+    pub query: Option<String>,
+    pub body: String,
+    headers: Vec<(String, String)>,
+}
+
+impl MockRequest {
+    /// Value of header `name`, case-insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Value of `key` in query, e.g. `level` of `/logs?level=info`
+    pub fn query_value(&self, key: &str) -> Option<String> {
+        self.query.as_ref()?.split('&').find_map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (k == key).then(|| decode(v))
+        })
+    }
+
+    fn read(reader: &mut impl BufRead) -> io::Result<Self> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => (method.to_owned(), target.to_owned()),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Bad request line",
+                ));
+            }
+        };
+
+        let mut headers = vec![];
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                headers.push((key.trim().to_owned(), value.trim().to_owned()))
+            }
+        }
+
+        let mut req = Self {
+            method,
+            path: String::new(),
+            query: None,
+            body: String::new(),
+            headers,
+        };
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_owned())),
+            None => (target.as_str(), None),
+        };
+        req.path = decode(path);
+        req.query = query;
+
+        let len = req
+            .header("Content-Length")
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body)?;
+        req.body = String::from_utf8_lossy(&body).into_owned();
+
+        Ok(req)
+    }
+}
+
+struct Shared {
+    state: MockState,
+    secret: Option<String>,
+    overrides: HashMap<(String, String), MockResponse>,
+    requests: Vec<MockRequest>,
+}
+
+/// Mock clash external controller, running until dropped
+pub struct MockClash {
+    addr: SocketAddr,
+    shared: Arc<Mutex<Shared>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockClash {
+    /// Start serving default fixtures on a random port
+    pub fn start() -> Self {
+        Self::with_state(MockState::default())
+    }
+
+    pub fn with_state(state: MockState) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Cannot bind mock server");
+        let addr = listener
+            .local_addr()
+            .expect("Cannot get address of mock server");
+        let shared = Arc::new(Mutex::new(Shared {
+            state,
+            secret: None,
+            overrides: HashMap::new(),
+            requests: vec![],
+        }));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let (thread_shared, thread_stopped) = (shared.clone(), stopped.clone());
+        spawn(move || {
+            for stream in listener.incoming() {
+                if thread_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let shared = thread_shared.clone();
+                spawn(move || {
+                    if let Err(e) = serve(stream, &shared) {
+                        debug!("Mock server error: {}", e)
+                    }
+                });
+            }
+        });
+        debug!("Mock server listening on {}", addr);
+
+        Self {
+            addr,
+            shared,
+            stopped,
+        }
+    }
+
+    /// Require requests to carry `secret`, as `Authorization: Bearer` header
+    /// or `token` query
+    pub fn secret<S: Into<String>>(self, secret: S) -> Self {
+        self.lock().secret = Some(secret.into());
+        self
+    }
+
+    /// Url of the mock server, e.g. `http://127.0.0.1:12345`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Client connected to the mock server, with its secret if any
+    pub fn clash(&self) -> Clash {
+        Clash::builder(self.url())
+            .expect("Url of mock server should be valid")
+            .secret(self.lock().secret.clone())
+            .build()
+    }
+
+    /// Respond to `method` on `path` with `response` instead, until
+    /// [`MockClash::reset`] is called
+    pub fn respond(&self, method: &str, path: &str, response: MockResponse) -> &Self {
+        self.lock()
+            .overrides
+            .insert((method.to_uppercase(), path.to_owned()), response);
+        self
+    }
+
+    /// Remove all overrides set by [`MockClash::respond`]
+    pub fn reset(&self) -> &Self {
+        self.lock().overrides.clear();
+        self
+    }
+
+    /// Change the data served
+    pub fn update(&self, f: impl FnOnce(&mut MockState)) -> &Self {
+        f(&mut self.lock().state);
+        self
+    }
+
+    /// Snapshot of the data served
+    pub fn state(&self) -> MockState {
+        self.lock().state.clone()
+    }
+
+    /// Requests received so far, in order
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap()
+    }
+}
+
+impl Drop for MockClash {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the listener so it can see the flag
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+fn serve(stream: TcpStream, shared: &Mutex<Shared>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let req = MockRequest::read(&mut reader)?;
+    trace!("Mock server received {:#?}", req);
+
+    let response = {
+        let mut shared = shared.lock().unwrap();
+        shared.requests.push(req.clone());
+        let key = (req.method.clone(), req.path.clone());
+        match shared.overrides.get(&key) {
+            Some(response) => response.clone(),
+            None if !authorized(&req, shared.secret.as_deref()) => MockResponse::status(401),
+            None => handle(&req, &mut shared.state),
+        }
+    };
+
+    if !response.delay.is_zero() {
+        sleep(response.delay)
+    }
+    write_response(stream, response)
+}
+
+fn authorized(req: &MockRequest, secret: Option<&str>) -> bool {
+    let Some(secret) = secret else { return true };
+    req.header("Authorization")
+        .and_then(|x| x.strip_prefix("Bearer "))
+        .is_some_and(|x| x == secret)
+        || req.query_value("token").is_some_and(|x| x == secret)
+}
+
+/// Default behavior of endpoints, similar to clash
+fn handle(req: &MockRequest, state: &mut MockState) -> MockResponse {
+    let segments = req.path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let no_content = |result: Result<(), u16>| match result {
+        Ok(_) => MockResponse::text("").with_status(204),
+        Err(status) => MockResponse::status(status),
+    };
+
+    match (req.method.as_str(), segments.as_slice()) {
+        ("GET", ["version"]) => MockResponse::json(&state.version),
+        ("GET", ["configs"]) => MockResponse::json(&state.configs),
+        ("PUT", ["configs"]) => no_content(Ok(())),
+        ("PATCH", ["configs"]) => no_content(
+            serde_json::from_str(&req.body)
+                .map_err(|_| 400)
+                .and_then(|patch| state.patch_configs(patch)),
+        ),
+        ("GET", ["proxies"]) => MockResponse::json(&state.proxies),
+        ("GET", ["proxies", name]) => match state.proxies.get(*name) {
+            Some(proxy) => MockResponse::json(proxy),
+            None => MockResponse::status(404),
+        },
+        ("PUT", ["proxies", name]) => {
+            let selected = serde_json::from_str::<Value>(&req.body)
+                .ok()
+                .and_then(|x| x.get("name")?.as_str().map(ToOwned::to_owned));
+            no_content(state.select(name, selected.as_deref()))
+        }
+        ("GET", ["proxies", _, "delay"]) if req.query_value("url").is_none() => {
+            MockResponse::status(400)
+        }
+        ("GET", ["proxies", name, "delay"]) => match state.delay(name) {
+            Ok(delay) => MockResponse::json(&json!({ "delay": delay })),
+            Err(status) => MockResponse::status(status),
+        },
+        ("GET", ["providers", "proxies"]) => MockResponse::json(&state.providers),
+        ("GET", ["providers", "proxies", name]) => match state.providers.get(*name) {
+            Some(provider) => MockResponse::json(provider),
+            None => MockResponse::status(404),
+        },
+        ("PUT", ["providers", "proxies", name]) => no_content(state.provider(name)),
+        ("GET", ["providers", "proxies", name, "healthcheck"]) => no_content(state.provider(name)),
+        ("GET", ["rules"]) => MockResponse::json(&state.rules),
+        ("GET", ["connections"]) => MockResponse::json(&state.connections),
+        ("DELETE", ["connections"]) => no_content(state.close_connections(None)),
+        ("DELETE", ["connections", id]) => no_content(state.close_connections(Some(id))),
+        ("GET", [endpoint @ ("traffic" | "logs")]) => {
+            match state.stream(endpoint, req.query_value("level").as_deref()) {
+                Ok(lines) => MockResponse::chunks(lines).interval(state.interval),
+                Err(status) => MockResponse::status(status),
+            }
+        }
+        _ => MockResponse::status(404),
+    }
+}
+
+fn write_response(mut stream: TcpStream, response: MockResponse) -> io::Result<()> {
+    let status = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    match response.body {
+        MockBody::Text(body) => {
+            write!(
+                stream,
+                "{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+                 close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )?;
+        }
+        MockBody::Chunks { chunks, interval } => {
+            write!(
+                stream,
+                "{}Content-Type: application/json\r\nTransfer-Encoding: chunked\r\nConnection: \
+                 close\r\n\r\n",
+                status
+            )?;
+            for chunk in chunks {
+                write!(stream, "{:x}\r\n{}\r\n", chunk.len(), chunk)?;
+                stream.flush()?;
+                if !interval.is_zero() {
+                    sleep(interval)
+                }
+            }
+            write!(stream, "0\r\n\r\n")?;
+        }
+    }
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Unknown",
+    }
+}
+
+fn decode(input: &str) -> String {
+    urlencoding::decode(input)
+        .map(|x| x.into_owned())
+        .unwrap_or_else(|_| input.to_owned())
+}
+
+/// In-memory [`ClashApi`] over [`MockState`], for tests of code generic over
+/// it. No HTTP is involved, so it's faster and more deterministic than
+/// [`MockClash`], but cannot test [`Clash`] itself.
+///
+/// Clones share the same state. Endpoints fail with the error of the status
+/// clash would respond with, see [`Error::from_response`], or the one set with
+/// [`FakeClash::fail`].
+/// Longhaul requests end after sending all items, like [`MockClash`].
+#[derive(Debug, Clone, Default)]
+pub struct FakeClash {
+    state: Arc<Mutex<MockState>>,
+    failures: Arc<Mutex<HashMap<&'static str, u16>>>,
+    calls: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl FakeClash {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_state(state: MockState) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            ..Self::default()
+        }
+    }
+
+    /// Make method `name` of [`ClashApi`], e.g. `get_proxies`, fail with
+    /// `status` from now on
+    pub fn fail(&self, name: &'static str, status: u16) -> &Self {
+        self.failures.lock().unwrap().insert(name, status);
+        self
+    }
+
+    /// Change the data served
+    pub fn update(&self, f: impl FnOnce(&mut MockState)) -> &Self {
+        f(&mut self.state.lock().unwrap());
+        self
+    }
+
+    /// Snapshot of the data served
+    pub fn state(&self) -> MockState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Names of methods called so far, in order
+    pub fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn call<T>(
+        &self,
+        name: &'static str,
+        f: impl FnOnce(&mut MockState) -> Result<T, u16>,
+    ) -> crate::Result<T> {
+        self.calls.lock().unwrap().push(name);
+        if let Some(status) = self.failures.lock().unwrap().get(name) {
+            return Err(Error::failed_response(*status));
+        }
+        f(&mut self.state.lock().unwrap()).map_err(Error::failed_response)
+    }
+}
+
+impl ClashApi for FakeClash {
+    fn get_version(&self) -> crate::Result<Version> {
+        self.call("get_version", |x| Ok(x.version.clone()))
+    }
+
+    fn get_configs(&self) -> crate::Result<Config> {
+        self.call("get_configs", |x| Ok(x.configs.clone()))
+    }
+
+    fn reload_configs(&self, _force: bool, _path: &str) -> crate::Result<()> {
+        self.call("reload_configs", |_| Ok(()))
+    }
+
+    fn patch_configs(&self, patch: &ConfigPatch) -> crate::Result<()> {
+        self.call("patch_configs", |x| x.patch_configs(json!(patch)))
+    }
+
+    fn get_proxies(&self) -> crate::Result<Proxies> {
+        self.call("get_proxies", |x| Ok(x.proxies.clone()))
+    }
+
+    fn get_proxy(&self, proxy: &str) -> crate::Result<Proxy> {
+        self.call("get_proxy", |x| x.proxies.get(proxy).cloned().ok_or(404))
+    }
+
+    fn get_proxy_delay(&self, proxy: &str, _test_url: &str, _timeout: u64) -> crate::Result<Delay> {
+        self.call("get_proxy_delay", |x| {
+            x.delay(proxy).map(|delay| Delay { delay })
+        })
+    }
+
+    fn set_proxygroup_selected(&self, group: &str, proxy: &str) -> crate::Result<()> {
+        self.call("set_proxygroup_selected", |x| x.select(group, Some(proxy)))
+    }
+
+    fn get_rules(&self) -> crate::Result<Rules> {
+        self.call("get_rules", |x| Ok(x.rules.clone()))
+    }
+
+    fn get_connections(&self) -> crate::Result<Connections> {
+        self.call("get_connections", |x| Ok(x.connections.clone()))
+    }
+
+    fn close_connections(&self) -> crate::Result<()> {
+        self.call("close_connections", |x| x.close_connections(None))
+    }
+
+    fn close_one_connection(&self, id: &str) -> crate::Result<()> {
+        self.call("close_one_connection", |x| x.close_connections(Some(id)))
+    }
+
+    fn get_providers(&self) -> crate::Result<Providers> {
+        self.call("get_providers", |x| Ok(x.providers.clone()))
+    }
+
+    fn get_provider(&self, provider: &str) -> crate::Result<Provider> {
+        self.call("get_provider", |x| {
+            x.providers.get(provider).cloned().ok_or(404)
+        })
+    }
+
+    fn update_provider(&self, provider: &str) -> crate::Result<()> {
+        self.call("update_provider", |x| x.provider(provider))
+    }
+
+    fn healthcheck_provider(&self, provider: &str) -> crate::Result<()> {
+        self.call("healthcheck_provider", |x| x.provider(provider))
+    }
+
+    fn longhaul_req<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        _method: &str,
+    ) -> crate::Result<LongHaul<T>> {
+        let (path, query) = endpoint.split_once('?').unwrap_or((endpoint, ""));
+        let level = query.strip_prefix("level=");
+        let lines = self.call("longhaul_req", |x| x.stream(path, level))?;
+        Ok(LongHaul::new(Box::new(Cursor::new(
+            lines.concat().into_bytes(),
+        ))))
+    }
+}
+
+#[test]
+fn test_fake_clash() {
+    use crate::model::Mode;
+
+    let clash = FakeClash::new();
+    clash.patch_configs(&Mode::Global.into()).unwrap();
+    assert_eq!(clash.get_configs().unwrap().mode, Mode::Global);
+    assert!(clash.set_proxygroup_selected("Auto", "US-01").is_err());
+    clash.set_proxygroup_selected("Proxy", "US-01").unwrap();
+    assert_eq!(clash.state().proxies["Proxy"].now.as_deref(), Some("US-01"));
+
+    let logs = clash
+        .get_log_with_level(Level::Warning)
+        .unwrap()
+        .map(|x| x.unwrap().log_type)
+        .collect::<Vec<_>>();
+    assert_eq!(logs, [Level::Warning, Level::Error]);
+
+    clash.fail("get_rules", 503);
+    assert!(matches!(
+        clash.get_rules().unwrap_err().kind(),
+        crate::ErrorKind::FailedResponse(503, None)
+    ));
+    assert_eq!(
+        clash.calls(),
+        [
+            "patch_configs",
+            "get_configs",
+            "set_proxygroup_selected",
+            "set_proxygroup_selected",
+            "longhaul_req",
+            "get_rules"
+        ]
+    );
+}
@@ -17,3 +17,58 @@ pub struct Config {
     pub bind_address: String,
     pub authentication: Vec<String>,
 }
+
+/// Partial update of [`Config`], used by `PATCH /configs`. Fields left as
+/// `None` are not changed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ConfigPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socks_port: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redir_port: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tproxy_port: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mixed_port: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_lan: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<Mode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<Level>,
+}
+
+impl ConfigPatch {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl From<Mode> for ConfigPatch {
+    fn from(mode: Mode) -> Self {
+        Self {
+            mode: Some(mode),
+            ..Self::default()
+        }
+    }
+}
+
+#[test]
+fn test_config_patch() {
+    let patch = ConfigPatch {
+        allow_lan: Some(false),
+        log_level: Some(Level::Warning),
+        ..Mode::Direct.into()
+    };
+    assert_eq!(
+        serde_json::to_string(&patch).unwrap(),
+        r#"{"allow-lan":false,"mode":"direct","log-level":"warning"}"#
+    );
+    assert!(ConfigPatch::default().is_empty());
+    assert_eq!(Mode::Direct.next(), Mode::Global);
+}
@@ -54,9 +54,6 @@ pub struct ConnectionsWithSpeed {
     pub upload_total: u64,
 }
 
-#[cfg(feature = "deserialize")]
-pub use deserialize::*;
-
 #[cfg(feature = "deserialize")]
 mod deserialize {
     use chrono::Utc;
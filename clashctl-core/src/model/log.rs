@@ -15,10 +15,171 @@ pub enum Level {
     Debug,
 }
 
-// TODO Parse log
+impl Level {
+    /// Name of the level used by clash, e.g. in `/logs?level=`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+
+    /// Next level in the order of Error, Warning, Info, Debug, wrapping around
+    pub fn next(&self) -> Self {
+        match self {
+            Level::Error => Level::Warning,
+            Level::Warning => Level::Info,
+            Level::Info => Level::Debug,
+            Level::Debug => Level::Error,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Log {
     #[serde(rename = "type")]
     pub log_type: Level,
     pub payload: String,
 }
+
+impl Log {
+    /// Parse the raw payload into [`LogPayload`]
+    pub fn parse(&self) -> LogPayload {
+        LogPayload::parse(&self.payload)
+    }
+}
+
+/// A connection being routed, e.g.
+/// `[TCP] 127.0.0.1:50000 --> example.com:443 match DomainSuffix(example.com)
+/// using Proxy[node]`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ConnectionLog {
+    /// `TCP` or `UDP`
+    pub network: String,
+    pub source: String,
+    pub destination: String,
+    /// Matched rule type, `None` when no rule is involved, e.g. in global mode
+    pub rule: Option<String>,
+    pub rule_payload: String,
+    /// Chosen proxy, in form of `group[proxy]` if chained
+    pub proxy: String,
+}
+
+/// Structured form of [`Log::payload`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogPayload {
+    Connection(ConnectionLog),
+    /// DNS lines, with the `[DNS]` tag stripped
+    Dns(String),
+    /// Lines containing `error:`, split into what was being done and the
+    /// error message
+    Error {
+        context: String,
+        message: String,
+    },
+    /// Payloads that cannot be recognized
+    Other(String),
+}
+
+impl LogPayload {
+    pub fn parse(payload: &str) -> Self {
+        let payload = payload.trim();
+
+        if let Some((context, message)) = payload.split_once(" error: ") {
+            return Self::Error {
+                context: context.to_owned(),
+                message: message.to_owned(),
+            };
+        }
+
+        if let Some(con) = Self::parse_connection(payload) {
+            return Self::Connection(con);
+        }
+
+        match payload.strip_prefix("[DNS] ") {
+            Some(dns) => Self::Dns(dns.to_owned()),
+            None => Self::Other(payload.to_owned()),
+        }
+    }
+
+    fn parse_connection(payload: &str) -> Option<ConnectionLog> {
+        let (network, rest) = payload.strip_prefix('[')?.split_once("] ")?;
+        if !matches!(network, "TCP" | "UDP") {
+            return None;
+        }
+        let (source, rest) = rest.split_once(" --> ")?;
+        let (destination, rest) = rest.split_once(' ')?;
+
+        let (rule, rule_payload, proxy) = if let Some(rest) = rest.strip_prefix("match ") {
+            let (rule, proxy) = rest.split_once(" using ")?;
+            let (rule, rule_payload) = rule.strip_suffix(')')?.split_once('(')?;
+            (Some(rule.to_owned()), rule_payload.to_owned(), proxy)
+        } else {
+            let proxy = rest
+                .strip_prefix("using ")
+                .or_else(|| rest.strip_prefix("doesn't match any rule using "))?;
+            (None, String::new(), proxy)
+        };
+
+        Some(ConnectionLog {
+            network: network.to_owned(),
+            source: source.to_owned(),
+            destination: destination.to_owned(),
+            rule,
+            rule_payload,
+            proxy: proxy.to_owned(),
+        })
+    }
+}
+
+#[test]
+fn test_log_payload() {
+    assert_eq!(
+        LogPayload::parse(
+            "[TCP] 127.0.0.1:50000 --> www.google.com:443 match DomainSuffix(google.com) using \
+             Proxy[HK-01]"
+        ),
+        LogPayload::Connection(ConnectionLog {
+            network: "TCP".to_owned(),
+            source: "127.0.0.1:50000".to_owned(),
+            destination: "www.google.com:443".to_owned(),
+            rule: Some("DomainSuffix".to_owned()),
+            rule_payload: "google.com".to_owned(),
+            proxy: "Proxy[HK-01]".to_owned(),
+        })
+    );
+    assert_eq!(
+        LogPayload::parse(
+            "[UDP] 127.0.0.1:5353 --> 8.8.8.8:53 doesn't match any rule using DIRECT"
+        ),
+        LogPayload::Connection(ConnectionLog {
+            network: "UDP".to_owned(),
+            source: "127.0.0.1:5353".to_owned(),
+            destination: "8.8.8.8:53".to_owned(),
+            rule: None,
+            rule_payload: String::new(),
+            proxy: "DIRECT".to_owned(),
+        })
+    );
+    assert_eq!(
+        LogPayload::parse(
+            "[TCP] dial Proxy (match Match/) 127.0.0.1:50000 --> example.com:443 error: i/o \
+             timeout"
+        ),
+        LogPayload::Error {
+            context: "[TCP] dial Proxy (match Match/) 127.0.0.1:50000 --> example.com:443"
+                .to_owned(),
+            message: "i/o timeout".to_owned()
+        }
+    );
+    assert_eq!(
+        LogPayload::parse("[DNS] example.com --> [93.184.216.34]"),
+        LogPayload::Dns("example.com --> [93.184.216.34]".to_owned())
+    );
+    assert_eq!(
+        LogPayload::parse("Start initial compatible provider default"),
+        LogPayload::Other("Start initial compatible provider default".to_owned())
+    );
+}
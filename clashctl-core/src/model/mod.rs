@@ -1,6 +1,8 @@
-mod_use::mod_use![config, connection, proxy, rule, traffic];
+mod_use::mod_use![config, connection, provider, proxy, rule, traffic];
 
 mod log;
+use std::fmt::{self, Display, Formatter};
+
 use cfg_if::cfg_if;
 use serde::{Deserialize, Serialize};
 
@@ -19,6 +21,17 @@ pub enum Mode {
     Direct,
 }
 
+impl Mode {
+    /// Next mode in the order of Global, Rule, Direct, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            Mode::Global => Mode::Rule,
+            Mode::Rule => Mode::Direct,
+            Mode::Direct => Mode::Global,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Delay {
     pub delay: u64,
@@ -49,12 +62,12 @@ pub enum VersionPayload {
     Raw(String),
 }
 
-impl ToString for VersionPayload {
-    fn to_string(&self) -> String {
+impl Display for VersionPayload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             #[cfg(feature = "deserialize")]
-            VersionPayload::SemVer(ver) => ver.to_string(),
-            VersionPayload::Raw(content) => content.to_owned(),
+            VersionPayload::SemVer(ver) => ver.fmt(f),
+            VersionPayload::Raw(content) => f.write_str(content),
         }
     }
 }
@@ -0,0 +1,136 @@
+use std::{collections::HashMap, ops::Deref};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Proxy, TimeType};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Providers {
+    pub providers: HashMap<String, Provider>,
+}
+
+impl Providers {
+    /// Providers that can be updated, i.e. not the `Compatible` one holding
+    /// proxies defined in config
+    pub fn updatable(&self) -> impl Iterator<Item = (&String, &Provider)> {
+        self.iter().filter(|(_, x)| x.vehicle_type.is_updatable())
+    }
+}
+
+impl Deref for Providers {
+    type Target = HashMap<String, Provider>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.providers
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Provider {
+    pub name: String,
+    pub vehicle_type: VehicleType,
+    pub proxies: Vec<ProviderProxy>,
+    // Absent for `Compatible` providers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<TimeType>,
+    // Clash.Meta only, for providers fetched from subscriptions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscription_info: Option<SubscriptionInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProviderProxy {
+    pub name: String,
+    #[serde(flatten)]
+    pub proxy: Proxy,
+}
+
+impl Deref for ProviderProxy {
+    type Target = Proxy;
+
+    fn deref(&self) -> &Self::Target {
+        &self.proxy
+    }
+}
+
+/// Traffic quota of a subscription, from its `Subscription-Userinfo` header
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct SubscriptionInfo {
+    /// Bytes uploaded
+    pub upload: u64,
+    /// Bytes downloaded
+    pub download: u64,
+    /// Bytes allowed in total, `0` if unlimited
+    pub total: u64,
+    /// Unix timestamp in seconds when the subscription expires, `0` if never
+    pub expire: u64,
+}
+
+impl SubscriptionInfo {
+    pub fn used(&self) -> u64 {
+        self.upload + self.download
+    }
+
+    /// Bytes left, `None` if unlimited
+    pub fn remaining(&self) -> Option<u64> {
+        (self.total != 0).then(|| self.total.saturating_sub(self.used()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+#[cfg_attr(
+    feature = "enum_ext",
+    derive(strum::EnumString, strum::Display, strum::EnumVariantNames),
+    strum(ascii_case_insensitive)
+)]
+pub enum VehicleType {
+    #[serde(rename = "HTTP")]
+    #[cfg_attr(feature = "enum_ext", strum(serialize = "HTTP"))]
+    Http,
+    File,
+    Compatible,
+    #[serde(other)]
+    Unknown,
+}
+
+impl VehicleType {
+    pub fn is_updatable(&self) -> bool {
+        matches!(self, VehicleType::Http | VehicleType::File)
+    }
+}
+
+#[test]
+fn test_provider() {
+    let providers: Providers = serde_json::from_str(
+        r#"{"providers": {
+            "default": {
+                "name": "default",
+                "type": "Proxy",
+                "vehicleType": "Compatible",
+                "proxies": [{ "name": "DIRECT", "type": "Direct", "history": [], "udp": true }]
+            },
+            "Sub": {
+                "name": "Sub",
+                "type": "Proxy",
+                "vehicleType": "HTTP",
+                "proxies": [{ "name": "HK-01", "type": "Shadowsocks", "history": [], "udp": true }],
+                "updatedAt": "2022-01-01T12:00:00.5+08:00",
+                "subscriptionInfo": { "Upload": 1, "Download": 2, "Total": 10, "Expire": 0 }
+            }
+        }}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        providers.updatable().map(|x| x.0).collect::<Vec<_>>(),
+        vec!["Sub"]
+    );
+    let sub = &providers["Sub"];
+    assert_eq!(sub.proxies[0].name, "HK-01");
+    assert!(sub.proxies[0].proxy_type.is_normal());
+    assert!(sub.updated_at.is_some());
+    assert_eq!(sub.subscription_info.unwrap().remaining(), Some(7));
+    assert_eq!(providers["default"].updated_at, None);
+}
@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::model::TimeType;
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 // #[serde(rename_all = "UPPERCASE")]
 #[cfg_attr(
@@ -40,6 +42,24 @@ pub struct Rule {
     pub proxy: String,
 }
 
+/// Hit statistics of a rule, aggregated from observed connections by
+/// [`RuleStatsTracker`](crate::RuleStatsTracker)
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RuleStats {
+    /// Number of connections matched by the rule
+    pub hits: usize,
+    /// Bytes uploaded and downloaded through these connections
+    pub traffic: u64,
+    /// Start time of the latest connection
+    pub last_hit: Option<TimeType>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RuleWithStats {
+    pub rule: Rule,
+    pub stats: RuleStats,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Rules {
     pub rules: Vec<Rule>,
@@ -0,0 +1,296 @@
+use std::{thread::sleep, time::Duration};
+
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+
+use crate::{api::Convert, Clash, ClashApi, Error, ErrorKind, LongHaul};
+
+/// Exponential backoff policy used by [`Reconnect`]
+///
+/// The n-th retry waits `initial * multiplier ^ (n - 1)`, capped at `max`.
+/// With `max_retries` set to `None`, [`Reconnect`] never gives up, except on
+/// errors retrying cannot fix, e.g. a wrong secret.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    multiplier: f64,
+    max_retries: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_retries: None,
+        }
+    }
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn initial(mut self, initial: Duration) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn max_retries(mut self, max_retries: Option<u32>) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before the `attempt`-th retry, starting from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.initial.as_secs_f64() * self.multiplier.powi(exp);
+        if secs.is_finite() && secs < self.max.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.max
+        }
+    }
+
+    /// Whether `attempt` is beyond the retry limit
+    pub fn exhausted(&self, attempt: u32) -> bool {
+        matches!(self.max_retries, Some(max) if attempt > max)
+    }
+}
+
+/// Connection state of a [`Reconnect`] stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamState {
+    /// The stream is (re-)established
+    Connected,
+    /// The stream is lost, will retry after `delay`
+    Reconnecting {
+        attempt: u32,
+        delay: Duration,
+        reason: String,
+    },
+    /// Retry limit reached, no more item will be yielded
+    GaveUp,
+}
+
+impl StreamState {
+    pub fn is_connected(&self) -> bool {
+        matches!(self, Self::Connected)
+    }
+}
+
+/// Item of [`Reconnect`], either data from the server or a state transition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent<T> {
+    Data(T),
+    State(StreamState),
+}
+
+/// A [`LongHaul`] that transparently re-establishes itself.
+///
+/// Connection is made lazily on first [`Iterator::next`]. Whenever the
+/// underlying stream ends or fails, a [`StreamState::Reconnecting`] is
+/// yielded and the next call blocks for the backoff delay before
+/// reconnecting. Items that fail to parse are logged and skipped.
+///
+/// Use [`Clash::longhaul_reconnect`] to create one.
+pub struct Reconnect<T: DeserializeOwned, C: ClashApi = Clash> {
+    clash: C,
+    endpoint: String,
+    backoff: Backoff,
+    current: Option<LongHaul<T>>,
+    attempt: u32,
+    wait: Option<Duration>,
+    gave_up: bool,
+}
+
+impl<T: DeserializeOwned, C: ClashApi> Reconnect<T, C> {
+    pub fn new<S: Into<String>>(clash: C, endpoint: S, backoff: Backoff) -> Self {
+        Self {
+            clash,
+            endpoint: endpoint.into(),
+            backoff,
+            current: None,
+            attempt: 0,
+            wait: None,
+            gave_up: false,
+        }
+    }
+
+    fn connect(&mut self) -> StreamEvent<T> {
+        if let Some(delay) = self.wait.take() {
+            sleep(delay)
+        }
+        debug!("Connecting to {}", self.endpoint);
+        match self.clash.longhaul_req(&self.endpoint, "GET") {
+            Ok(stream) => {
+                self.current = Some(stream);
+                StreamState::Connected.into()
+            }
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Retry after `error`, unless retrying cannot fix it
+    fn fail(&mut self, error: Error) -> StreamEvent<T> {
+        let terminal = matches!(
+            error.kind(),
+            ErrorKind::Unauthorized(_) | ErrorKind::NotFound(_)
+        );
+        self.retry(error.to_string(), terminal)
+    }
+
+    fn retry(&mut self, reason: String, terminal: bool) -> StreamEvent<T> {
+        self.current = None;
+        self.attempt += 1;
+        if terminal || self.backoff.exhausted(self.attempt) {
+            warn!("Giving up on {} ({})", self.endpoint, reason);
+            self.gave_up = true;
+            return StreamState::GaveUp.into();
+        }
+        let delay = self.backoff.delay(self.attempt);
+        self.wait = Some(delay);
+        StreamState::Reconnecting {
+            attempt: self.attempt,
+            delay,
+            reason,
+        }
+        .into()
+    }
+}
+
+impl<T: DeserializeOwned, C: ClashApi> Iterator for Reconnect<T, C> {
+    type Item = StreamEvent<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.gave_up {
+            return None;
+        }
+        loop {
+            let stream = match self.current {
+                Some(ref mut stream) => stream,
+                None => return Some(self.connect()),
+            };
+            match stream.next_raw() {
+                Some(Ok(raw)) => match raw.convert() {
+                    // Only a stream that works resets backoff, not one closed right away
+                    Ok(item) => {
+                        self.attempt = 0;
+                        return Some(StreamEvent::Data(item));
+                    }
+                    Err(e) => warn!("{:?}", e),
+                },
+                Some(Err(e)) => return Some(self.fail(e)),
+                None => return Some(self.retry("Stream closed by server".to_owned(), false)),
+            }
+        }
+    }
+}
+
+impl<T> From<StreamState> for StreamEvent<T> {
+    fn from(state: StreamState) -> Self {
+        Self::State(state)
+    }
+}
+
+#[test]
+fn test_backoff() {
+    let backoff = Backoff::new()
+        .initial(Duration::from_secs(1))
+        .max(Duration::from_secs(10))
+        .max_retries(Some(5));
+    assert_eq!(backoff.delay(1), Duration::from_secs(1));
+    assert_eq!(backoff.delay(2), Duration::from_secs(2));
+    assert_eq!(backoff.delay(4), Duration::from_secs(8));
+    assert_eq!(backoff.delay(5), Duration::from_secs(10));
+    assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(10));
+    assert!(!backoff.exhausted(5));
+    assert!(backoff.exhausted(6));
+    assert!(!Backoff::default().exhausted(u32::MAX));
+}
+
+#[test]
+fn test_reconnect_give_up() {
+    use crate::model::Traffic;
+
+    // Bind then drop to get a port that nobody listens on
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let clash = Clash::builder(format!("http://127.0.0.1:{}", port))
+        .unwrap()
+        .build();
+    let backoff = Backoff::new()
+        .initial(Duration::from_millis(1))
+        .max_retries(Some(1));
+    let events = clash
+        .longhaul_reconnect::<Traffic>("traffic", backoff)
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        StreamEvent::State(StreamState::Reconnecting { attempt: 1, .. })
+    ));
+    assert_eq!(events[1], StreamEvent::State(StreamState::GaveUp));
+}
+
+#[test]
+fn test_reconnect_closed_at_once() {
+    use std::{io::Write, net::TcpListener, thread};
+
+    use crate::model::Traffic;
+
+    // Accept every stream, then close it without sending anything
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let _ =
+                stream.write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n");
+        }
+    });
+    let clash = Clash::builder(format!("http://127.0.0.1:{}", port))
+        .unwrap()
+        .build();
+    let backoff = Backoff::new()
+        .initial(Duration::from_millis(1))
+        .max_retries(Some(2));
+    let states = clash
+        .longhaul_reconnect::<Traffic>("traffic", backoff)
+        .filter_map(|x| match x {
+            StreamEvent::State(StreamState::Reconnecting { attempt, .. }) => Some(attempt),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(states, [1, 2]);
+}
+
+#[test]
+fn test_reconnect_unauthorized() {
+    use crate::{mock::MockClash, model::Traffic};
+
+    let mock = MockClash::start().secret("secret");
+    let clash = Clash::builder(mock.url()).unwrap().build();
+    let events = clash
+        .longhaul_reconnect::<Traffic>("traffic", Backoff::new())
+        .collect::<Vec<_>>();
+
+    assert_eq!(events, [StreamEvent::State(StreamState::GaveUp)]);
+}
@@ -1,97 +1,182 @@
-use std::{env, sync::Once};
+use std::{
+    env,
+    sync::Once,
+    time::{Duration, Instant},
+};
 
-use home::home_dir;
 use log::info;
 
-use crate::Clash;
+use crate::{
+    mock::{MockClash, MockResponse},
+    model::{Level, Mode, Traffic},
+    Clash, ErrorKind,
+};
 
 static INIT: Once = Once::new();
 
-fn init() -> Clash {
+fn init() -> (MockClash, Clash) {
     INIT.call_once(|| {
         if env::var("RUST_LOG").is_err() {
             env::set_var("RUST_LOG", "DEBUG")
         }
         pretty_env_logger::init()
     });
-    Clash::builder(env::var("PROXY_ADDR").unwrap())
-        .unwrap()
-        .secret(env::var("PROXY_SECRET").ok())
-        .build()
+    let mock = MockClash::start();
+    let clash = mock.clash();
+    (mock, clash)
 }
 
 #[test]
 fn test_proxies() {
-    let clash = init();
-    clash.get_proxies().unwrap();
+    let (_mock, clash) = init();
+    let proxies = clash.get_proxies().unwrap();
+    assert_eq!(proxies.normal().count(), 2);
+    assert_eq!(proxies.selectors().count(), 2);
+}
+
+#[test]
+fn test_providers() {
+    let (mock, clash) = init();
+    assert_eq!(clash.get_providers().unwrap(), mock.state().providers);
+    let provider = clash.get_provider("Subscription").unwrap();
+    assert_eq!(provider.proxies.len(), 2);
+    clash.update_provider("Subscription").unwrap();
+    clash.healthcheck_provider("Subscription").unwrap();
+    assert!(matches!(
+        clash.update_provider("Nowhere").unwrap_err().kind(),
+        ErrorKind::NotFound(_)
+    ));
+
+    let requests = mock.requests();
+    let last = &requests[requests.len() - 2];
+    assert_eq!(
+        (last.method.as_str(), last.path.as_str()),
+        ("GET", "/providers/proxies/Subscription/healthcheck")
+    );
 }
 
 #[test]
 fn test_rules() {
-    let clash = init();
-    clash.get_rules().unwrap();
+    let (mock, clash) = init();
+    assert_eq!(clash.get_rules().unwrap(), mock.state().rules);
 }
 
 #[test]
 fn test_proxy() {
-    let clash = init();
+    let (_mock, clash) = init();
     let proxies = clash.get_proxies().unwrap();
     let (proxy, _) = proxies.iter().next().unwrap();
     clash.get_proxy(proxy).unwrap();
+    assert!(clash.get_proxy("Nowhere").is_err());
 }
 
 #[test]
 fn test_proxy_delay() {
-    let clash = init();
+    let (mock, clash) = init();
+    mock.update(|state| {
+        state.delays.insert("HK-01".to_owned(), 42);
+    });
     let proxies = clash.get_proxies().unwrap();
     let (proxy, _) = proxies.iter().find(|x| x.1.proxy_type.is_normal()).unwrap();
     clash
         .get_proxy_delay(proxy, "https://static.miao.dev/generate_204", 10000)
         .unwrap();
+    assert_eq!(
+        clash
+            .get_proxy_delay("HK-01", "https://static.miao.dev/generate_204", 10000)
+            .unwrap()
+            .delay,
+        42
+    );
 }
 
 #[test]
 fn test_set_proxy() {
-    let clash = init();
+    let (mock, clash) = init();
     let proxies = clash.get_proxies().unwrap();
     if let Some((group, proxy)) = proxies
         .iter()
         .find(|(_, proxy)| proxy.proxy_type.is_selector())
     {
         let all = proxy.all.as_ref().unwrap();
-        let member = all.iter().next().unwrap();
+        let member = all.iter().last().unwrap();
         clash.set_proxygroup_selected(group, member).unwrap();
+        assert_eq!(mock.state().proxies[group].now.as_ref(), Some(member));
     }
+    assert!(clash.set_proxygroup_selected("Proxy", "Nowhere").is_err());
+
+    // Group name goes in path and proxy name in JSON body, both need escaping
+    mock.update(|state| {
+        let mut group = state.proxies["Proxy"].clone();
+        group.all = Some(vec![r#"HK "Fast""#.to_owned()]);
+        state.proxies.proxies.insert("Video #1".to_owned(), group);
+    });
+    clash
+        .set_proxygroup_selected("Video #1", r#"HK "Fast""#)
+        .unwrap();
+    assert_eq!(
+        mock.state().proxies["Video #1"].now.as_deref(),
+        Some(r#"HK "Fast""#)
+    );
 }
 
 #[test]
 fn test_configs() {
-    let clash = init();
-    let default_config_dir = home_dir()
-        .expect("Home dir should exist")
-        .join(".config/clash/config.yaml");
-    let _path = default_config_dir.to_str().unwrap();
+    let (mock, clash) = init();
+    let path = "/etc/clash/config.yaml";
 
     clash.get_configs().unwrap();
-    // clash.reload_configs(false, path).unwrap();
-    // clash.reload_configs(true, path).unwrap();
+    clash.reload_configs(false, path).unwrap();
+    clash.reload_configs(true, path).unwrap();
+
+    let requests = mock.requests();
+    let reload = &requests[requests.len() - 1];
+    assert_eq!(
+        (reload.method.as_str(), reload.path.as_str()),
+        ("PUT", "/configs")
+    );
+    assert_eq!(reload.query.as_deref(), Some("force"));
+    assert_eq!(reload.body, r#"{"path":"/etc/clash/config.yaml"}"#);
+}
+
+#[test]
+fn test_patch_configs() {
+    let (_mock, clash) = init();
+    let mode = clash.get_configs().unwrap().mode;
+    clash.patch_configs(&mode.next().into()).unwrap();
+    assert_eq!(clash.get_configs().unwrap().mode, mode.next());
+    clash.patch_configs(&mode.into()).unwrap();
+    assert_eq!(clash.get_configs().unwrap().mode, Mode::Rule);
 }
 
 #[test]
 fn test_traffic() {
-    let clash = init();
-    clash.get_traffic().unwrap().next();
+    let (mock, clash) = init();
+    assert_eq!(
+        clash
+            .get_traffic()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>(),
+        mock.state().traffic
+    );
 }
 
 #[test]
 fn test_log() {
-    let clash = init();
-    clash.get_log().unwrap().next();
+    let (_mock, clash) = init();
+    assert_eq!(clash.get_log().unwrap().count(), 4);
+    let logs = clash
+        .get_log_with_level(Level::Warning)
+        .unwrap()
+        .map(|x| x.unwrap().log_type)
+        .collect::<Vec<_>>();
+    assert_eq!(logs, vec![Level::Warning, Level::Error]);
 }
 
 #[test]
 fn test_connections() {
-    let clash = init();
+    let (mock, clash) = init();
     let cons = clash.get_connections().unwrap();
     let res = &cons
         .connections
@@ -99,11 +184,115 @@ fn test_connections() {
         .expect("Should exist at least one connection")
         .id;
     clash.close_one_connection(res).unwrap();
+    assert_eq!(mock.state().connections.connections.len(), 1);
     clash.close_connections().unwrap();
+    assert!(clash.get_connections().unwrap().connections.is_empty());
 }
 
 #[test]
 fn test_version() {
-    let clash = init();
+    let (_mock, clash) = init();
     info!("{:#?}", clash.get_version().unwrap())
 }
+
+#[test]
+fn test_secret() {
+    let (mock, _) = init();
+    let mock = mock.secret("secret");
+    mock.clash().get_version().unwrap();
+
+    let clash = Clash::builder(mock.url())
+        .unwrap()
+        .secret(Some("wrong".to_owned()))
+        .build();
+    let err = clash.get_version().unwrap_err();
+    assert!(
+        matches!(err.kind(), ErrorKind::Unauthorized(Some(ref x)) if x == "Unauthorized"),
+        "{:?}",
+        err
+    );
+    assert_eq!(
+        mock.requests()[0].header("authorization"),
+        Some("Bearer secret")
+    );
+}
+
+#[test]
+fn test_error_response() {
+    let (mock, clash) = init();
+    mock.respond("GET", "/rules", MockResponse::status(500));
+    assert!(matches!(
+        clash.get_rules().unwrap_err().kind(),
+        ErrorKind::FailedResponse(500, Some(_))
+    ));
+    assert!(matches!(
+        clash.get_proxy("NotExist").unwrap_err().kind(),
+        ErrorKind::NotFound(_)
+    ));
+    mock.respond("GET", "/rules", MockResponse::text("{\"rules\":"));
+    assert!(matches!(
+        clash.get_rules().unwrap_err().kind(),
+        ErrorKind::BadResponseFormat(_)
+    ));
+    mock.reset();
+    clash.get_rules().unwrap();
+}
+
+#[test]
+fn test_connection_refused() {
+    // Grab a free port and close it, so nothing is listening
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let clash = Clash::builder(format!("http://127.0.0.1:{}", port))
+        .unwrap()
+        .build();
+    assert!(matches!(
+        clash.get_version().unwrap_err().kind(),
+        ErrorKind::ConnectionRefused(_)
+    ));
+}
+
+#[test]
+fn test_timeout() {
+    let (mock, _) = init();
+    mock.respond(
+        "GET",
+        "/version",
+        MockResponse::text("{}").delay(Duration::from_millis(500)),
+    );
+    let clash = Clash::builder(mock.url())
+        .unwrap()
+        .timeout(Some(Duration::from_millis(100)))
+        .build();
+    let start = Instant::now();
+    assert!(matches!(
+        clash.get_version().unwrap_err().kind(),
+        ErrorKind::Timeout
+    ));
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
+
+#[test]
+fn test_split_chunks() {
+    let (mock, clash) = init();
+    mock.respond(
+        "GET",
+        "/traffic",
+        MockResponse::chunks(vec![
+            "{\"up\":1,".to_owned(),
+            "\"down\":2}\n{\"up\"".to_owned(),
+            ":3,\"down\":4}\n".to_owned(),
+        ]),
+    );
+    assert_eq!(
+        clash
+            .get_traffic()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>(),
+        vec![Traffic { up: 1, down: 2 }, Traffic { up: 3, down: 4 }]
+    );
+}
@@ -0,0 +1,75 @@
+use futures_util::{stream, StreamExt};
+
+use crate::{mock::MockClash, model::Traffic, AsyncClash, AsyncLongHaul};
+
+fn init() -> (MockClash, AsyncClash) {
+    let mock = MockClash::start().secret("secret");
+    let clash = AsyncClash::builder(mock.url())
+        .unwrap()
+        .secret(Some("secret".to_owned()))
+        .build_async();
+    (mock, clash)
+}
+
+#[tokio::test]
+async fn test_async_proxies() {
+    let (mock, clash) = init();
+    assert_eq!(clash.get_proxies().await.unwrap(), mock.state().proxies);
+}
+
+#[tokio::test]
+async fn test_async_rules() {
+    let (mock, clash) = init();
+    assert_eq!(clash.get_rules().await.unwrap(), mock.state().rules);
+}
+
+#[tokio::test]
+async fn test_async_providers() {
+    let (mock, clash) = init();
+    assert_eq!(clash.get_providers().await.unwrap(), mock.state().providers);
+    clash.update_provider("Subscription").await.unwrap();
+    assert!(clash.healthcheck_provider("Nowhere").await.is_err());
+}
+
+#[tokio::test]
+async fn test_async_version() {
+    let (_mock, clash) = init();
+    clash.get_version().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_async_traffic() {
+    let (mock, clash) = init();
+    let traffics = clash
+        .get_traffic()
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(traffics, mock.state().traffic);
+}
+
+#[tokio::test]
+async fn test_async_longhaul_split() {
+    let chunks = [
+        "{\"up\":1,",
+        "\"down\":2}\n{\"up\"",
+        ":3,\"down\":4}\n",
+        "{\"up\":5,\"down\":6}",
+    ]
+    .into_iter()
+    .map(|x| Ok(x.as_bytes().to_vec().into()));
+    let traffics = AsyncLongHaul::<Traffic>::new(Box::pin(stream::iter(chunks)))
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        traffics,
+        vec![
+            Traffic { up: 1, down: 2 },
+            Traffic { up: 3, down: 4 },
+            Traffic { up: 5, down: 6 }
+        ]
+    );
+}
@@ -1 +1,3 @@
 mod api;
+#[cfg(feature = "async")]
+mod async_api;
@@ -0,0 +1,301 @@
+use std::{collections::HashMap, time::Instant};
+
+use crate::model::{
+    Connection, ConnectionWithSpeed, Connections, ConnectionsWithSpeed, Rule, RuleStats, RuleType,
+    RuleWithStats, Rules,
+};
+
+/// Result of feeding a snapshot to [`ConnectionTracker`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedConnections {
+    /// Connections in the snapshot, with speed since last snapshot
+    pub connections: ConnectionsWithSpeed,
+    /// Connections that are not in last snapshot
+    pub opened: Vec<Connection>,
+    /// Connections in last snapshot that are gone, with their final stats
+    pub closed: Vec<Connection>,
+    /// Overall upload speed since last snapshot, in bytes per second
+    pub upload_speed: Option<u64>,
+    /// Overall download speed since last snapshot, in bytes per second
+    pub download_speed: Option<u64>,
+}
+
+/// Computes instantaneous speed of connections by diffing successive
+/// [`Connections`] snapshots by `id`.
+///
+/// Snapshots can either be polled with
+/// [`Clash::get_connections`](crate::Clash::get_connections) or streamed.
+/// Speed of a connection is `None` the first time it's seen.
+///
+/// ```rust
+/// # use clashctl_core::{ConnectionTracker, model::Connections};
+/// let mut tracker = ConnectionTracker::new();
+/// let tracked = tracker.track(Connections::default());
+/// assert!(tracked.connections.connections.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionTracker {
+    last: HashMap<String, Connection>,
+    last_totals: (u64, u64),
+    last_time: Option<Instant>,
+}
+
+impl ConnectionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a snapshot received just now
+    pub fn track(&mut self, snapshot: Connections) -> TrackedConnections {
+        self.track_at(snapshot, Instant::now())
+    }
+
+    /// Feed a snapshot received at `now`
+    pub fn track_at(&mut self, snapshot: Connections, now: Instant) -> TrackedConnections {
+        let elapsed = self
+            .last_time
+            .map(|last| now.saturating_duration_since(last).as_secs_f64())
+            .filter(|secs| *secs > 0.0);
+        let rate = |prev: u64, cur: u64| {
+            elapsed.map(|secs| (cur.saturating_sub(prev) as f64 / secs) as u64)
+        };
+
+        let mut last = std::mem::take(&mut self.last);
+        let mut opened = vec![];
+        let connections = snapshot
+            .connections
+            .into_iter()
+            .map(|con| {
+                let (upload, download) = match last.remove(&con.id) {
+                    Some(prev) => (
+                        rate(prev.upload, con.upload),
+                        rate(prev.download, con.download),
+                    ),
+                    None => {
+                        opened.push(con.clone());
+                        (None, None)
+                    }
+                };
+                self.last.insert(con.id.clone(), con.clone());
+                ConnectionWithSpeed {
+                    connection: con,
+                    upload,
+                    download,
+                }
+            })
+            .collect();
+        let closed = last.into_values().collect();
+
+        let upload_speed = rate(self.last_totals.0, snapshot.upload_total);
+        let download_speed = rate(self.last_totals.1, snapshot.download_total);
+        self.last_totals = (snapshot.upload_total, snapshot.download_total);
+        self.last_time = Some(now);
+
+        TrackedConnections {
+            connections: ConnectionsWithSpeed {
+                connections,
+                download_total: snapshot.download_total,
+                upload_total: snapshot.upload_total,
+            },
+            opened,
+            closed,
+            upload_speed,
+            download_speed,
+        }
+    }
+
+    /// Forget all previous snapshots, e.g. after reconnecting to another server
+    pub fn reset(&mut self) {
+        *self = Self::default()
+    }
+}
+
+/// Aggregates connections by the rule they matched into [`RuleStats`], by
+/// diffing successive snapshots like [`ConnectionTracker`]. Stats are kept
+/// after connections are closed.
+#[derive(Debug, Clone, Default)]
+pub struct RuleStatsTracker {
+    stats: HashMap<(RuleType, String), RuleStats>,
+    /// Traffic of connections in last snapshot, by id
+    last: HashMap<String, u64>,
+}
+
+impl RuleStatsTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed all connections of a snapshot
+    pub fn track<'a>(&mut self, connections: impl IntoIterator<Item = &'a Connection>) {
+        let mut last = std::mem::take(&mut self.last);
+        for con in connections {
+            let traffic = con.upload + con.download;
+            let stats = self
+                .stats
+                .entry((con.rule, con.rule_payload.to_owned()))
+                .or_default();
+            match last.remove(&con.id) {
+                Some(prev) => stats.traffic += traffic.saturating_sub(prev),
+                None => {
+                    stats.hits += 1;
+                    stats.traffic += traffic;
+                    // `TimeType` is only `Copy` with feature `deserialize`
+                    #[allow(clippy::clone_on_copy)]
+                    let start = con.start.clone();
+                    stats.last_hit = stats.last_hit.take().max(Some(start));
+                }
+            }
+            self.last.insert(con.id.to_owned(), traffic);
+        }
+    }
+
+    pub fn get(&self, rule: &Rule) -> Option<&RuleStats> {
+        self.stats.get(&(rule.rule_type, rule.payload.to_owned()))
+    }
+
+    /// Pair each rule with its stats, rules that are never hit get empty stats
+    pub fn with_stats(&self, rules: &Rules) -> Vec<RuleWithStats> {
+        rules
+            .rules
+            .iter()
+            .map(|rule| RuleWithStats {
+                rule: rule.to_owned(),
+                stats: self.get(rule).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Forget all stats, e.g. after reconnecting to another server
+    pub fn reset(&mut self) {
+        *self = Self::default()
+    }
+}
+
+#[test]
+fn test_tracker() {
+    use std::time::Duration;
+
+    let con = |id: &str, upload: u64, download: u64| -> Connection {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "upload": upload,
+            "download": download,
+            "metadata": {
+                "type": "HTTP",
+                "sourceIP": "127.0.0.1",
+                "sourcePort": "50000",
+                "destinationIP": "",
+                "destinationPort": "443",
+                "host": "example.com",
+                "network": "tcp"
+            },
+            "rule": "Match",
+            "rulePayload": "",
+            "start": "2022-01-01T00:00:00Z",
+            "chains": ["DIRECT"]
+        }))
+        .unwrap()
+    };
+    let snapshot = |connections: Vec<Connection>| Connections {
+        upload_total: connections.iter().map(|x| x.upload).sum(),
+        download_total: connections.iter().map(|x| x.download).sum(),
+        connections,
+    };
+
+    let mut tracker = ConnectionTracker::new();
+    let t0 = Instant::now();
+
+    let first = tracker.track_at(snapshot(vec![con("a", 100, 1000), con("b", 0, 0)]), t0);
+    assert_eq!(first.opened.len(), 2);
+    assert!(first.closed.is_empty());
+    assert_eq!(first.upload_speed, None);
+    assert!(first
+        .connections
+        .connections
+        .iter()
+        .all(|x| x.upload.is_none() && x.download.is_none()));
+
+    let second = tracker.track_at(
+        snapshot(vec![con("a", 300, 1000), con("c", 10, 10)]),
+        t0 + Duration::from_secs(2),
+    );
+    assert_eq!(
+        second.opened.iter().map(|x| &x.id).collect::<Vec<_>>(),
+        ["c"]
+    );
+    assert_eq!(
+        second.closed.iter().map(|x| &x.id).collect::<Vec<_>>(),
+        ["b"]
+    );
+    let a = &second.connections.connections[0];
+    assert_eq!((a.upload, a.download), (Some(100), Some(0)));
+    assert_eq!(second.upload_speed, Some(105));
+    assert_eq!(second.download_speed, Some(5));
+}
+
+#[test]
+fn test_rule_stats_tracker() {
+    let con = |id: &str, traffic: u64, rule: &str, payload: &str, start: &str| -> Connection {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "upload": 0,
+            "download": traffic,
+            "metadata": {
+                "type": "HTTP",
+                "sourceIP": "127.0.0.1",
+                "sourcePort": "50000",
+                "destinationIP": "",
+                "destinationPort": "443",
+                "host": "www.google.com",
+                "network": "tcp"
+            },
+            "rule": rule,
+            "rulePayload": payload,
+            "start": start,
+            "chains": ["Proxy"]
+        }))
+        .unwrap()
+    };
+    let rule = |rule_type, payload: &str| Rule {
+        rule_type,
+        payload: payload.to_owned(),
+        proxy: "Proxy".to_owned(),
+    };
+    let google = rule(RuleType::DomainSuffix, "google.com");
+
+    let mut tracker = RuleStatsTracker::new();
+    let a = con(
+        "a",
+        100,
+        "DomainSuffix",
+        "google.com",
+        "2022-01-01T00:00:00Z",
+    );
+    let b = con(
+        "b",
+        50,
+        "DomainSuffix",
+        "google.com",
+        "2022-01-02T00:00:00Z",
+    );
+    tracker.track([&a, &b]);
+    tracker.track([&con(
+        "a",
+        300,
+        "DomainSuffix",
+        "google.com",
+        "2022-01-01T00:00:00Z",
+    )]);
+    // `b` is closed, and `c` is opened
+    tracker.track([&con("c", 10, "Match", "", "2022-01-03T00:00:00Z")]);
+
+    let stats = tracker.get(&google).unwrap();
+    assert_eq!((stats.hits, stats.traffic), (2, 350));
+    assert_eq!(stats.last_hit, Some(b.start));
+
+    let rules = Rules {
+        rules: vec![google, rule(RuleType::Domain, "example.com")],
+    };
+    let with_stats = tracker.with_stats(&rules);
+    assert_eq!(with_stats[1].stats, RuleStats::default());
+}
@@ -0,0 +1,158 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    io::{Cursor, Read},
+    time::Duration,
+};
+
+use ureq::Agent;
+use url::Url;
+
+use crate::Result;
+
+/// HTTP request made by [`Clash`](crate::Clash)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: Url,
+    /// Headers to send, e.g. `Authorization` if secret is set
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    /// `None` for longhaul requests, which last until interrupted
+    pub timeout: Option<Duration>,
+}
+
+/// HTTP response returned by [`HttpTransport`]. Body is read lazily, so
+/// longhaul requests can stream from it.
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Box<dyn Read + Send>,
+}
+
+impl HttpResponse {
+    /// Response with an in-memory body
+    pub fn new<S: Into<String>>(status: u16, body: S) -> Self {
+        Self {
+            status,
+            body: Box::new(Cursor::new(body.into().into_bytes())),
+        }
+    }
+
+    /// Response streaming from `reader`
+    pub fn from_reader(status: u16, reader: Box<dyn Read + Send>) -> Self {
+        Self {
+            status,
+            body: reader,
+        }
+    }
+}
+
+impl Debug for HttpResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpResponse")
+            .field("status", &self.status)
+            .finish_non_exhaustive()
+    }
+}
+
+/// HTTP stack used by [`Clash`](crate::Clash), [`UreqTransport`] by default.
+///
+/// Error statuses should be returned as responses, not errors, so that
+/// [`Clash`](crate::Clash) handles them the same way for all transports.
+/// Wrap another transport to add logging, retries or headers, or implement it
+/// in memory to test code using [`Clash`](crate::Clash) without a server.
+/// Closures with the same signature as [`HttpTransport::send`] are transports
+/// too.
+///
+/// WebSocket connections of [`LongHaulTransport::WebSocket`] do not go
+/// through this.
+///
+/// [`LongHaulTransport::WebSocket`]: crate::LongHaulTransport
+pub trait HttpTransport: Send + Sync {
+    fn send(&self, req: HttpRequest) -> Result<HttpResponse>;
+}
+
+impl<F> HttpTransport for F
+where
+    F: Fn(HttpRequest) -> Result<HttpResponse> + Send + Sync,
+{
+    fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+        self(req)
+    }
+}
+
+/// Default [`HttpTransport`], backed by [`ureq`]
+#[derive(Debug, Clone)]
+pub struct UreqTransport {
+    agent: Agent,
+}
+
+impl UreqTransport {
+    pub fn new(agent: Agent) -> Self {
+        Self { agent }
+    }
+}
+
+impl Default for UreqTransport {
+    fn default() -> Self {
+        Self::new(Agent::new())
+    }
+}
+
+impl HttpTransport for UreqTransport {
+    fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+        let mut request = self.agent.request_url(&req.method, &req.url);
+        if let Some(timeout) = req.timeout {
+            request = request.timeout(timeout)
+        }
+        for (key, value) in &req.headers {
+            request = request.set(key, value)
+        }
+        let resp = match req.body {
+            Some(ref body) => request.send_string(body),
+            None => request.call(),
+        };
+        match resp {
+            Ok(resp) | Err(ureq::Error::Status(_, resp)) => {
+                Ok(HttpResponse::from_reader(resp.status(), resp.into_reader()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[test]
+fn test_closure_transport() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{model::Mode, Clash, ErrorKind};
+
+    let sent = Arc::new(Mutex::new(vec![]));
+    let record = sent.clone();
+    let clash = Clash::builder("http://clash.local:9090")
+        .unwrap()
+        .secret(Some("secret".to_owned()))
+        .http_transport(move |req: HttpRequest| {
+            let resp = match (req.method.as_str(), req.url.path()) {
+                ("GET", "/version") => HttpResponse::new(200, r#"{"version":"1.10.0"}"#),
+                ("PATCH", "/configs") => HttpResponse::new(204, ""),
+                _ => HttpResponse::new(404, r#"{"message":"Not Found"}"#),
+            };
+            record.lock().unwrap().push(req);
+            Ok(resp)
+        })
+        .build();
+
+    clash.get_version().unwrap();
+    clash.patch_configs(&Mode::Global.into()).unwrap();
+    assert!(matches!(
+        clash.get_rules().unwrap_err().kind(),
+        ErrorKind::NotFound(Some(ref x)) if x == "Not Found"
+    ));
+
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent.len(), 3);
+    assert_eq!(sent[1].body.as_deref(), Some(r#"{"mode":"global"}"#));
+    assert!(sent
+        .iter()
+        .all(|x| x.headers == [("Authorization".to_owned(), "Bearer secret".to_owned())]));
+}
@@ -0,0 +1,124 @@
+use std::net::TcpStream;
+
+use log::debug;
+use tungstenite::{stream::MaybeTlsStream, Message};
+use url::Url;
+
+use crate::{Error, Result};
+
+pub(crate) type WsStream = tungstenite::WebSocket<MaybeTlsStream<TcpStream>>;
+
+/// Turn `http://` (or `https://`) controller url into `ws://` (or `wss://`)
+/// one of the endpoint.
+///
+/// Browsers cannot set headers on WebSocket, so clash accepts secret as query
+/// `token` instead of `Authorization` header.
+pub(crate) fn ws_url(base: &Url, endpoint: &str, secret: Option<&str>) -> Result<Url> {
+    let mut url = base.join(endpoint).map_err(|_| Error::url_parse())?;
+    let scheme = match url.scheme() {
+        "http" | "ws" => "ws",
+        "https" | "wss" => "wss",
+        other => return Err(Error::other(format!("Unsupported scheme {}", other))),
+    };
+    url.set_scheme(scheme).map_err(|_| Error::url_parse())?;
+    if let Some(secret) = secret {
+        url.query_pairs_mut().append_pair("token", secret);
+    }
+    Ok(url)
+}
+
+pub(crate) fn ws_connect(url: Url) -> Result<WsStream> {
+    debug!("Connecting to {}", url);
+    match tungstenite::connect(url) {
+        Ok((ws, _)) => Ok(ws),
+        Err(tungstenite::Error::Http(resp)) => {
+            let body = resp.body().as_deref().unwrap_or_default();
+            Err(Error::from_response(
+                resp.status().as_u16(),
+                &String::from_utf8_lossy(body),
+            ))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Read next text frame. Control frames are skipped and a closed connection is
+/// treated as end of stream.
+pub(crate) fn ws_next_text(ws: &mut WsStream) -> Option<Result<String>> {
+    loop {
+        match ws.read() {
+            Ok(Message::Text(text)) => return Some(Ok(text)),
+            Ok(Message::Binary(bin)) => {
+                return Some(String::from_utf8(bin).map_err(|_| Error::bad_response_encoding()))
+            }
+            Ok(Message::Close(_)) => return None,
+            Ok(_) => continue,
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                return None
+            }
+            Err(e) => return Some(Err(e.into())),
+        }
+    }
+}
+
+#[test]
+fn test_ws_url() {
+    let base = Url::parse("http://127.0.0.1:9090/").unwrap();
+    assert_eq!(
+        ws_url(&base, "traffic", None).unwrap().as_str(),
+        "ws://127.0.0.1:9090/traffic"
+    );
+
+    let base = Url::parse("https://example.com/clash/").unwrap();
+    assert_eq!(
+        ws_url(&base, "logs?level=info", Some("a b&c"))
+            .unwrap()
+            .as_str(),
+        "wss://example.com/clash/logs?level=info&token=a+b%26c"
+    );
+}
+
+#[test]
+// Callback signature is dictated by tungstenite
+#[allow(clippy::result_large_err)]
+fn test_ws_longhaul() {
+    use std::{net::TcpListener, thread};
+
+    use tungstenite::handshake::server::Request;
+
+    use crate::{model::Traffic, Clash, LongHaulTransport};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut path = String::new();
+        let mut ws = tungstenite::accept_hdr(stream, |req: &Request, resp| {
+            path = req.uri().to_string();
+            Ok(resp)
+        })
+        .unwrap();
+        ws.send(Message::text(r#"{"up":1,"down":2}"#)).unwrap();
+        ws.send(Message::text(r#"{"up":3,"down":4}"#)).unwrap();
+        ws.close(None).unwrap();
+        while ws.read().is_ok() {}
+        path
+    });
+
+    let clash = Clash::builder(format!("http://{}", addr))
+        .unwrap()
+        .secret(Some("secret".to_owned()))
+        .transport(LongHaulTransport::WebSocket)
+        .build();
+    let traffics = clash
+        .get_traffic()
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+
+    assert_eq!(
+        traffics,
+        vec![Traffic { up: 1, down: 2 }, Traffic { up: 3, down: 4 }]
+    );
+    assert_eq!(server.join().unwrap(), "/traffic?token=secret");
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use clap::{Parser, Subcommand};
+use clashctl_core::{
+    model::{Proxies, Rule, Rules},
+    ClashApi,
+};
+use log::info;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::{interactive::Flags, Error, Result};
+
+#[derive(Subcommand, Debug)]
+#[clap(about = "Interacting with configs of clash")]
+pub enum ConfigSubcommand {
+    #[clap(about = "Reload configs from file and show what changed")]
+    Reload(ConfigReloadOpt),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConfigReloadOpt {
+    #[clap(
+        help = "Absolute path of config file on the host of clash. Default to config path of \
+                current server"
+    )]
+    pub path: Option<String>,
+
+    #[clap(short, long, help = "Also apply changes of ports etc.")]
+    pub force: bool,
+}
+
+/// Items added to, removed from or changed in a list
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    fn print(&self, title: &str) {
+        println!(
+            "{}: {} {} {}",
+            title,
+            format!("+{}", self.added.len()).green(),
+            format!("-{}", self.removed.len()).red(),
+            format!("~{}", self.changed.len()).yellow()
+        );
+        self.added
+            .iter()
+            .for_each(|x| println!("  {}", format!("+ {}", x).green()));
+        self.removed
+            .iter()
+            .for_each(|x| println!("  {}", format!("- {}", x).red()));
+        self.changed
+            .iter()
+            .for_each(|x| println!("  {}", format!("~ {}", x).yellow()));
+    }
+}
+
+/// Difference of proxies and rules before and after reloading
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReloadDiff {
+    /// Proxies and groups, by name. Changed ones have different type or
+    /// members
+    pub proxies: Changes,
+    /// Rules, as `TYPE,PAYLOAD,PROXY`
+    pub rules: Changes,
+    /// Whether rules are the same but in a different order
+    pub rules_reordered: bool,
+}
+
+impl ReloadDiff {
+    pub fn new(proxies: (&Proxies, &Proxies), rules: (&Rules, &Rules)) -> Self {
+        let (before, after) = proxies;
+        let mut proxies = Changes::default();
+        for (name, proxy) in after.iter() {
+            match before.get(name) {
+                None => proxies.added.push(name.to_owned()),
+                Some(old) if old.proxy_type != proxy.proxy_type || old.all != proxy.all => {
+                    proxies.changed.push(name.to_owned())
+                }
+                _ => {}
+            }
+        }
+        proxies.removed = before
+            .keys()
+            .filter(|name| !after.contains_key(*name))
+            .cloned()
+            .collect();
+        proxies.added.sort();
+        proxies.removed.sort();
+        proxies.changed.sort();
+
+        // Rules can be duplicated, so compare them as multisets
+        let (before, after) = rules;
+        let mut counts = HashMap::<&Rule, isize>::new();
+        before
+            .rules
+            .iter()
+            .for_each(|x| *counts.entry(x).or_default() -= 1);
+        after
+            .rules
+            .iter()
+            .for_each(|x| *counts.entry(x).or_default() += 1);
+        let mut rules = Changes::default();
+        for (rule, count) in counts {
+            let line = format!("{},{},{}", rule.rule_type, rule.payload, rule.proxy);
+            let target = if count > 0 {
+                &mut rules.added
+            } else {
+                &mut rules.removed
+            };
+            (0..count.unsigned_abs()).for_each(|_| target.push(line.clone()));
+        }
+        rules.added.sort();
+        rules.removed.sort();
+        let rules_reordered = rules.is_empty() && before.rules != after.rules;
+
+        Self {
+            proxies,
+            rules,
+            rules_reordered,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proxies.is_empty() && self.rules.is_empty() && !self.rules_reordered
+    }
+}
+
+impl ConfigSubcommand {
+    pub fn handle(&self, flags: &Flags) -> Result<()> {
+        match self {
+            Self::Reload(opt) => opt.handle(flags),
+        }
+    }
+}
+
+impl ConfigReloadOpt {
+    pub fn handle(&self, flags: &Flags) -> Result<()> {
+        let path = match self.path {
+            Some(ref path) => path.to_owned(),
+            None => flags
+                .get_server(&flags.get_config()?)?
+                .and_then(|server| server.config_path)
+                .ok_or_else(|| {
+                    Error::bad_option("No path given and current server has no config path")
+                })?,
+        };
+        // Path is on the host of clash, so it's passed as is
+        self.run(&flags.connect_server_from_config()?, &path, flags)
+    }
+
+    /// Reload from `path`, which is already resolved
+    pub fn run<C: ClashApi>(&self, clash: &C, path: &str, flags: &Flags) -> Result<()> {
+        let (proxies, rules) = (clash.get_proxies()?, clash.get_rules()?);
+
+        info!("Reloading configs from {}", path);
+        clash.reload_configs(self.force, path)?;
+
+        let diff = ReloadDiff::new(
+            (&proxies, &clash.get_proxies()?),
+            (&rules, &clash.get_rules()?),
+        );
+        flags.output.print_or(&diff, || {
+            if diff.is_empty() {
+                println!("Reloaded, nothing changed");
+                return;
+            }
+            diff.proxies.print("Proxies");
+            diff.rules.print("Rules");
+            if diff.rules_reordered {
+                println!("Rules: {}", "reordered".yellow());
+            }
+        })
+    }
+}
+
+#[test]
+fn test_reload_diff() {
+    use clashctl_core::model::{Proxy, ProxyType, RuleType};
+
+    let proxy = |proxy_type, all: Option<&[&str]>| Proxy {
+        proxy_type,
+        history: vec![],
+        udp: None,
+        all: all.map(|x| x.iter().map(|x| x.to_string()).collect()),
+        now: None,
+    };
+    let proxies = |list: Vec<(&str, Proxy)>| Proxies {
+        proxies: list.into_iter().map(|(k, v)| (k.to_owned(), v)).collect(),
+    };
+    let rule = |payload: &str, proxy: &str| Rule {
+        rule_type: RuleType::DomainSuffix,
+        payload: payload.to_owned(),
+        proxy: proxy.to_owned(),
+    };
+
+    let before = proxies(vec![
+        ("HK", proxy(ProxyType::Shadowsocks, None)),
+        ("US", proxy(ProxyType::Vmess, None)),
+        ("Proxy", proxy(ProxyType::Selector, Some(&["HK", "US"]))),
+    ]);
+    let after = proxies(vec![
+        ("HK", proxy(ProxyType::Shadowsocks, None)),
+        ("JP", proxy(ProxyType::Trojan, None)),
+        ("Proxy", proxy(ProxyType::Selector, Some(&["HK", "JP"]))),
+    ]);
+    let rules = Rules {
+        rules: vec![rule("google.com", "Proxy"), rule("baidu.com", "DIRECT")],
+    };
+    let reversed = Rules {
+        rules: rules.rules.iter().rev().cloned().collect(),
+    };
+
+    let diff = ReloadDiff::new((&before, &after), (&rules, &reversed));
+    assert_eq!(diff.proxies.added, vec!["JP"]);
+    assert_eq!(diff.proxies.removed, vec!["US"]);
+    assert_eq!(diff.proxies.changed, vec!["Proxy"]);
+    assert!(diff.rules.is_empty() && diff.rules_reordered);
+
+    let mut more = rules.clone();
+    more.rules.push(rule("google.com", "Proxy"));
+    let diff = ReloadDiff::new((&before, &before), (&rules, &more));
+    assert!(diff.proxies.is_empty());
+    assert_eq!(diff.rules.added, vec!["DomainSuffix,google.com,Proxy"]);
+    assert!(diff.rules.removed.is_empty() && !diff.rules_reordered);
+}
@@ -0,0 +1,422 @@
+use std::{thread::sleep, time::Duration};
+
+use bytesize::ByteSize;
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use clashctl_core::{
+    model::{Connection, ConnectionWithSpeed, RuleType},
+    strum::VariantNames,
+    ClashApi, ConnectionTracker,
+};
+use log::{info, warn};
+use owo_colors::OwoColorize;
+use terminal_size::{terminal_size, Height, Width};
+
+use crate::{
+    interactive::{ConSort, ConSortBy, Flags, SortOrder, Sortable},
+    Error, OutputFormat, Result, HMS,
+};
+
+#[derive(Subcommand, Debug)]
+#[clap(about = "Interacting with connections")]
+pub enum ConnSubcommand {
+    #[clap(alias = "ls", about = "List connections (alias ls)")]
+    List(ConnListOpt),
+    #[clap(about = "Close connections by id (prefix) or by filters")]
+    Close(ConnCloseOpt),
+    #[clap(about = "Close all connections")]
+    CloseAll,
+    #[clap(about = "Continuously list connections with speed")]
+    Watch(ConnWatchOpt),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConnFilterOpt {
+    #[clap(
+        long,
+        help = "Only connections whose host contains this, case-insensitive"
+    )]
+    pub host: Option<String>,
+
+    #[clap(long, help = "Only connections from this source IP, or IP prefix")]
+    pub src: Option<String>,
+
+    #[clap(
+        long,
+        help = "Only connections matched by this type of rule",
+        possible_values = RuleType::VARIANTS,
+        ignore_case = true
+    )]
+    pub rule: Option<RuleType>,
+
+    #[clap(long, help = "Only connections going through this proxy or group")]
+    pub chain: Option<String>,
+
+    #[clap(
+        long,
+        help = "Only connections with at least this much traffic, e.g. 10MB"
+    )]
+    pub min_traffic: Option<ByteSize>,
+}
+
+impl ConnFilterOpt {
+    pub fn is_empty(&self) -> bool {
+        self.host.is_none()
+            && self.src.is_none()
+            && self.rule.is_none()
+            && self.chain.is_none()
+            && self.min_traffic.is_none()
+    }
+
+    pub fn matches(&self, con: &Connection) -> bool {
+        let meta = &con.metadata;
+        self.host
+            .as_ref()
+            .is_none_or(|host| meta.host.to_lowercase().contains(&host.to_lowercase()))
+            && self
+                .src
+                .as_ref()
+                .is_none_or(|src| meta.source_ip.starts_with(src.as_str()))
+            && self.rule.is_none_or(|rule| con.rule == rule)
+            && self
+                .chain
+                .as_ref()
+                .is_none_or(|chain| con.chains.contains(chain))
+            && self
+                .min_traffic
+                .is_none_or(|min| con.upload + con.download >= min.as_u64())
+    }
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConnSortOpt {
+    #[clap(
+        long,
+        default_value = "time",
+        possible_values = ConSortBy::VARIANTS,
+        ignore_case = true
+    )]
+    pub sort_by: ConSortBy,
+
+    #[clap(
+        long,
+        default_value = "descendant",
+        possible_values = &["ascendant", "descendant"],
+    )]
+    pub sort_order: SortOrder,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConnListOpt {
+    #[clap(flatten)]
+    pub filter: ConnFilterOpt,
+
+    #[clap(flatten)]
+    pub sort: ConnSortOpt,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConnCloseOpt {
+    #[clap(
+        help = "Id, or unique prefix of id, of connections to close. Filters apply to them if \
+                given"
+    )]
+    pub ids: Vec<String>,
+
+    #[clap(flatten)]
+    pub filter: ConnFilterOpt,
+
+    #[clap(long, help = "Only print connections that would be closed")]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConnWatchOpt {
+    #[clap(flatten)]
+    pub filter: ConnFilterOpt,
+
+    #[clap(flatten)]
+    pub sort: ConnSortOpt,
+
+    #[clap(short, long, default_value = "1")]
+    /// Interval between refreshes, in seconds
+    pub interval: f32,
+}
+
+impl ConnSubcommand {
+    pub fn handle(&self, flags: &Flags) -> Result<()> {
+        self.run(&flags.connect_server_from_config()?, flags)
+    }
+
+    pub fn run<C: ClashApi>(&self, clash: &C, flags: &Flags) -> Result<()> {
+        match self {
+            Self::List(opt) => {
+                if matches!(opt.sort.sort_by, ConSortBy::DownSpeed | ConSortBy::UpSpeed) {
+                    return Err(Error::bad_option(
+                        "Speeds are only measured by `clashctl conn watch`",
+                    ));
+                }
+                let cons = clash
+                    .get_connections()?
+                    .connections
+                    .into_iter()
+                    .filter(|con| opt.filter.matches(con))
+                    .map(|connection| ConnectionWithSpeed {
+                        connection,
+                        upload: None,
+                        download: None,
+                    })
+                    .collect();
+                render(cons, &opt.sort, false, flags.output)?;
+            }
+            Self::Close(opt) => close(clash, opt)?,
+            Self::CloseAll => {
+                let count = clash.get_connections()?.connections.len();
+                clash.close_connections()?;
+                info!("Closed {} connection(s)", count)
+            }
+            Self::Watch(opt) => {
+                let mut tracker = ConnectionTracker::new();
+                let interval = Duration::from_secs_f32(opt.interval);
+                loop {
+                    let mut tracked = tracker.track(clash.get_connections()?).connections;
+                    tracked
+                        .connections
+                        .retain(|con| opt.filter.matches(&con.connection));
+                    if flags.output.is_table() {
+                        // Clear screen and move cursor to top left
+                        print!("\x1b[2J\x1b[H");
+                    }
+                    render(tracked.connections, &opt.sort, true, flags.output)?;
+                    sleep(interval);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn close<C: ClashApi>(clash: &C, opt: &ConnCloseOpt) -> Result<()> {
+    if opt.ids.is_empty() && opt.filter.is_empty() {
+        return Err(Error::bad_option(
+            "No id or filter given. Use `clashctl conn close-all` to close all connections",
+        ));
+    }
+
+    let cons = clash.get_connections()?.connections;
+    let mut targets = if opt.ids.is_empty() {
+        cons.iter().collect()
+    } else {
+        let mut targets = vec![];
+        for id in &opt.ids {
+            let mut found = cons.iter().filter(|con| con.id.starts_with(id.as_str()));
+            match (found.next(), found.next()) {
+                (Some(con), None) => targets.push(con),
+                (None, _) => {
+                    return Err(Error::bad_option(format!("No connection with id {}", id)))
+                }
+                (Some(_), Some(_)) => {
+                    return Err(Error::bad_option(format!(
+                        "Id prefix {} is ambiguous, use a longer one",
+                        id
+                    )))
+                }
+            }
+        }
+        targets
+    };
+    targets.retain(|con| opt.filter.matches(con));
+    targets.sort_by(|a, b| a.id.cmp(&b.id));
+    targets.dedup_by(|a, b| a.id == b.id);
+    if targets.is_empty() {
+        return Err(Error::bad_option(
+            "No connection matches given ids and filters",
+        ));
+    }
+
+    if opt.dry_run {
+        for con in targets {
+            println!("{} {}", con.id, con.metadata.host);
+        }
+        return Ok(());
+    }
+
+    let (total, mut closed) = (targets.len(), 0);
+    for con in targets {
+        match clash.close_one_connection(&con.id) {
+            Ok(_) => {
+                closed += 1;
+                info!("Closed {} ({})", con.id, con.metadata.host.green())
+            }
+            Err(e) => warn!("Failed to close {}: {}", con.id, e),
+        }
+    }
+    info!("Closed {} connection(s)", closed);
+    if closed < total {
+        return Err(clashctl_core::Error::other(format!(
+            "Failed to close {} of {} connection(s)",
+            total - closed,
+            total
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+fn render(
+    mut cons: Vec<ConnectionWithSpeed>,
+    opt: &ConnSortOpt,
+    with_speed: bool,
+    output: OutputFormat,
+) -> Result<()> {
+    cons.sort_with(&ConSort::new(opt.sort_by, opt.sort_order));
+
+    // Speeds are only measured when watching
+    if with_speed {
+        output.print_or(&cons, || render_table(&cons, with_speed))
+    } else {
+        let connections = cons.iter().map(|x| &x.connection).collect::<Vec<_>>();
+        output.print_or(&connections, || render_table(&cons, with_speed))
+    }
+}
+
+fn render_table(cons: &[ConnectionWithSpeed], with_speed: bool) {
+    let (Width(terminal_width), _) = terminal_size().unwrap_or((Width(70), Height(0)));
+    let speed_header = if with_speed {
+        format!("{:<12}{:<12}", "DOWN/S", "UP/S")
+    } else {
+        String::new()
+    };
+    println!("\n{:-<1$}", "", terminal_width as usize);
+    println!(
+        "{:<10}{:<12}{:<12}{}{:<15} {:<16}{:<45}CHAINS",
+        "ID", "DOWN", "UP", speed_header, "TIME", "RULE", "HOST"
+    );
+    println!("{:-<1$}", "", terminal_width as usize);
+
+    for ConnectionWithSpeed {
+        connection: con,
+        upload,
+        download,
+    } in cons
+    {
+        let meta = &con.metadata;
+        let host = if meta.host.is_empty() {
+            &meta.destination_ip
+        } else {
+            &meta.host
+        };
+        let speed = if with_speed {
+            format!(
+                "{:<12}{:<12}",
+                ByteSize(download.unwrap_or_default()).to_string_as(true) + "/s",
+                ByteSize(upload.unwrap_or_default()).to_string_as(true) + "/s"
+            )
+        } else {
+            String::new()
+        };
+        println!(
+            "{:<10}{:<12}{:<12}{}{:<15} {:<16}{:<45}{}",
+            con.id.chars().take(8).collect::<String>().dimmed(),
+            ByteSize(con.download).to_string_as(true),
+            ByteSize(con.upload).to_string_as(true),
+            speed,
+            (Utc::now() - con.start).hms(),
+            con.rule.to_string(),
+            format!("{}:{}", host, meta.destination_port).green(),
+            con.chains.join(" - ")
+        );
+    }
+    println!("{:-<1$}", "", terminal_width as usize);
+}
+
+#[test]
+fn test_conn_filter() {
+    let con: Connection = serde_json::from_str(
+        r#"{
+            "id": "5c2ad6ba-6bd4-4b55-8d2b-3c4b6d8a1e0f",
+            "upload": 1024,
+            "download": 4096,
+            "metadata": {
+                "type": "HTTP",
+                "sourceIP": "192.168.1.10",
+                "sourcePort": "50000",
+                "destinationIP": "",
+                "destinationPort": "443",
+                "host": "www.Example.com",
+                "network": "tcp"
+            },
+            "rule": "DomainSuffix",
+            "rulePayload": "example.com",
+            "start": "2022-01-01T00:00:00Z",
+            "chains": ["HK-01", "Proxy"]
+        }"#,
+    )
+    .unwrap();
+    let filter = |args: &[&str]| {
+        ConnFilterOpt::try_parse_from(std::iter::once("filter").chain(args.iter().copied()))
+            .unwrap()
+    };
+
+    assert!(filter(&[]).matches(&con));
+    assert!(filter(&["--host", "example"]).matches(&con));
+    assert!(filter(&["--src", "192.168.1."]).matches(&con));
+    assert!(filter(&["--rule", "domainsuffix", "--chain", "Proxy"]).matches(&con));
+    assert!(filter(&["--min-traffic", "5KiB"]).matches(&con));
+    assert!(!filter(&["--min-traffic", "6KiB"]).matches(&con));
+    assert!(!filter(&["--host", "example", "--chain", "DIRECT"]).matches(&con));
+    assert!(!filter(&["--src", "10."]).matches(&con));
+}
+
+#[test]
+fn test_conn_close() {
+    use clashctl_core::mock::FakeClash;
+
+    let clash = FakeClash::new();
+    let close = |args: &[&str]| {
+        close(
+            &clash,
+            &ConnCloseOpt::parse_from(std::iter::once("close").chain(args.iter().copied())),
+        )
+    };
+
+    assert!(close(&[]).is_err(), "Should not close all without filter");
+    close(&["--dry-run", "--host", "google"]).unwrap();
+    assert_eq!(clash.state().connections.connections.len(), 2);
+
+    close(&["--host", "google"]).unwrap();
+    let left = clash.state().connections.connections;
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].metadata.host, "github.com");
+
+    // Filters apply to given ids too
+    assert!(close(&["2", "--host", "google"]).is_err());
+    assert_eq!(clash.state().connections.connections.len(), 1);
+    assert!(
+        close(&["2", "3"]).is_err(),
+        "Should not close with unknown id"
+    );
+    close(&["2"]).unwrap();
+    assert!(clash.state().connections.connections.is_empty());
+
+    clash.update(|state| state.connections = FakeClash::new().state().connections);
+    clash.fail("close_one_connection", 500);
+    assert!(close(&["1"]).is_err());
+}
+
+#[test]
+fn test_conn_list_sort() {
+    use clashctl_core::mock::FakeClash;
+
+    let clash = FakeClash::new();
+    let flags = Flags::default();
+    let list = |args: &[&str]| {
+        ConnSubcommand::List(ConnListOpt::parse_from([&["list"], args].concat()))
+            .run(&clash, &flags)
+    };
+
+    list(&["--sort-by", "Host"]).unwrap();
+    list(&["--sort-by", "dest", "--sort-order", "ascendant"]).unwrap();
+    assert!(list(&["--sort-by", "downspeed"]).is_err());
+    assert!(ConnListOpt::try_parse_from(["list", "--sort-by", "nothing"]).is_err());
+}
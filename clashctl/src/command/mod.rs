@@ -3,7 +3,7 @@ use log::LevelFilter;
 
 use crate::{interactive::Flags, ui::TuiOpt, utils::init_logger};
 
-mod_use::mod_use!(completion, proxy, server);
+mod_use::mod_use!(completion, config, conn, mode, provider, proxy, proxy_test, rule, server);
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -27,7 +27,16 @@ pub enum Cmd {
     #[clap(subcommand)]
     Proxy(ProxySubcommand),
     #[clap(subcommand)]
+    Provider(ProviderSubcommand),
+    #[clap(subcommand)]
     Server(ServerSubcommand),
+    #[clap(subcommand)]
+    Conn(ConnSubcommand),
+    Mode(ModeArg),
+    #[clap(subcommand)]
+    Config(ConfigSubcommand),
+    #[clap(subcommand)]
+    Rule(RuleSubcommand),
     #[clap(alias = "comp")]
     Completion(CompletionArg),
 }
@@ -45,3 +54,13 @@ impl Opts {
         });
     }
 }
+
+#[test]
+fn test_global_flags() {
+    use clap::CommandFactory;
+
+    Opts::command().debug_assert();
+    let opts = Opts::parse_from(["clashctl", "proxy", "list", "-o", "json", "--server", "a"]);
+    assert!(!opts.flag.output.is_table());
+    assert_eq!(opts.flag.server.as_deref(), Some("a"));
+}
@@ -0,0 +1,57 @@
+use clap::Parser;
+use clashctl_core::{model::Mode, strum::VariantNames, ClashApi};
+use log::info;
+
+use crate::{interactive::Flags, Result};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Get or set proxy mode of clash")]
+pub struct ModeArg {
+    #[clap(
+        help = "Mode to switch to. Print current mode if omitted",
+        possible_values = Mode::VARIANTS,
+        ignore_case = true
+    )]
+    pub mode: Option<Mode>,
+}
+
+impl ModeArg {
+    pub fn handle(&self, flags: &Flags) -> Result<()> {
+        self.run(&flags.connect_server_from_config()?, flags)
+    }
+
+    pub fn run<C: ClashApi>(&self, clash: &C, flags: &Flags) -> Result<()> {
+        match self.mode {
+            Some(mode) => {
+                clash.patch_configs(&mode.into())?;
+                info!("Mode set to {}", mode);
+            }
+            None => {
+                let mode = clash.get_configs()?.mode;
+                flags.output.print_or(&mode, || println!("{}", mode))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_mode() {
+    use clashctl_core::mock::FakeClash;
+
+    let clash = FakeClash::new();
+    let flags = Flags::default();
+    ModeArg::parse_from(["mode", "GLOBAL"])
+        .run(&clash, &flags)
+        .unwrap();
+    assert_eq!(clash.state().configs.mode, Mode::Global);
+
+    ModeArg::parse_from(["mode"]).run(&clash, &flags).unwrap();
+    assert_eq!(clash.calls(), ["patch_configs", "get_configs"]);
+
+    clash.fail("patch_configs", 400);
+    assert!(ModeArg::parse_from(["mode", "direct"])
+        .run(&clash, &flags)
+        .is_err());
+    assert_eq!(clash.state().configs.mode, Mode::Global);
+}
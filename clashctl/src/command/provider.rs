@@ -0,0 +1,234 @@
+use bytesize::ByteSize;
+use chrono::{TimeZone, Utc};
+use clap::{Parser, Subcommand};
+use clashctl_core::{
+    model::{Provider, Providers},
+    ClashApi,
+};
+use log::{info, warn};
+use owo_colors::OwoColorize;
+use terminal_size::{terminal_size, Height, Width};
+
+use crate::{interactive::Flags, Error, Result, HMS};
+
+#[derive(Subcommand, Debug)]
+#[clap(about = "Interacting with proxy providers")]
+pub enum ProviderSubcommand {
+    #[clap(alias = "ls", about = "List proxy providers (alias ls)")]
+    List(ProviderListOpt),
+    #[clap(about = "Fetch proxies of providers again from their sources")]
+    Update(ProviderNamesOpt),
+    #[clap(about = "Test latency of all proxies in providers")]
+    Healthcheck(ProviderNamesOpt),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ProviderListOpt {
+    #[clap(short, long, help = "Also list proxies of each provider")]
+    pub proxies: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ProviderNamesOpt {
+    #[clap(help = "Names of providers, case-insensitive. All of them if omitted")]
+    pub names: Vec<String>,
+}
+
+impl ProviderNamesOpt {
+    /// Providers named in options, or all of those `filter` accepts if none is
+    /// named
+    pub fn select<'a>(
+        &self,
+        providers: &'a Providers,
+        filter: impl Fn(&Provider) -> bool,
+    ) -> Result<Vec<&'a Provider>> {
+        if self.names.is_empty() {
+            return Ok(sorted(providers)
+                .into_iter()
+                .filter(|x| filter(x))
+                .collect());
+        }
+        self.names
+            .iter()
+            .map(|name| {
+                providers
+                    .values()
+                    .find(|x| x.name.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| Error::bad_option(format!("No provider named {}", name)))
+            })
+            .collect()
+    }
+}
+
+impl ProviderSubcommand {
+    pub fn handle(&self, flags: &Flags) -> Result<()> {
+        self.run(&flags.connect_server_from_config()?, flags)
+    }
+
+    pub fn run<C: ClashApi>(&self, clash: &C, flags: &Flags) -> Result<()> {
+        match self {
+            Self::List(opt) => {
+                let providers = clash.get_providers()?;
+                let list = sorted(&providers);
+                flags
+                    .output
+                    .print_or(&list, || render_table(&list, opt.proxies))
+            }
+            Self::Update(opt) => {
+                let providers = clash.get_providers()?;
+                let targets = opt.select(&providers, |x| x.vehicle_type.is_updatable())?;
+                let mut failed = vec![];
+                for provider in targets {
+                    match clash.update_provider(&provider.name) {
+                        Ok(_) => info!("Updated {}", provider.name.green()),
+                        Err(e) => {
+                            warn!("Failed to update {}: {}", provider.name, e);
+                            failed.push(provider.name.as_str())
+                        }
+                    }
+                }
+                check_failed("update", &failed)
+            }
+            Self::Healthcheck(opt) => {
+                let providers = clash.get_providers()?;
+                let (mut checked, mut failed) = (vec![], vec![]);
+                for provider in opt.select(&providers, |_| true)? {
+                    match clash
+                        .healthcheck_provider(&provider.name)
+                        .and_then(|_| clash.get_provider(&provider.name))
+                    {
+                        Ok(provider) => checked.push(provider),
+                        Err(e) => {
+                            warn!("Failed to check {}: {}", provider.name, e);
+                            failed.push(provider.name.as_str())
+                        }
+                    }
+                }
+                let list = checked.iter().collect::<Vec<_>>();
+                flags.output.print_or(&list, || render_table(&list, true))?;
+                check_failed("check", &failed)
+            }
+        }
+    }
+}
+
+/// Error naming providers that failed to `action`, if any
+fn check_failed(action: &str, failed: &[&str]) -> Result<()> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+    Err(clashctl_core::Error::other(format!(
+        "Failed to {} {} provider(s): {}",
+        action,
+        failed.len(),
+        failed.join(", ")
+    ))
+    .into())
+}
+
+/// Providers sorted by name
+fn sorted(providers: &Providers) -> Vec<&Provider> {
+    let mut list = providers.values().collect::<Vec<_>>();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+fn render_table(providers: &[&Provider], with_proxies: bool) {
+    let (Width(terminal_width), _) = terminal_size().unwrap_or((Width(70), Height(0)));
+    println!("\n{:-<1$}", "", terminal_width as usize);
+    println!(
+        "{:<20}{:<12}{:<9}{:<20}{:<24}EXPIRE",
+        "NAME", "TYPE", "PROXIES", "UPDATED", "USED / TOTAL"
+    );
+    println!("{:-<1$}", "", terminal_width as usize);
+    for provider in providers {
+        let updated = provider
+            .updated_at
+            .map(|time| format!("{} ago", (Utc::now() - time).hms()))
+            .unwrap_or_else(|| "-".to_owned());
+        let (quota, expire) = match provider.subscription_info {
+            Some(info) => (
+                format!(
+                    "{} / {}",
+                    ByteSize(info.used()).to_string_as(true),
+                    match info.total {
+                        0 => "∞".to_owned(),
+                        total => ByteSize(total).to_string_as(true),
+                    }
+                ),
+                match Utc.timestamp_opt(info.expire as i64, 0).single() {
+                    Some(time) if info.expire != 0 => time.format("%Y-%m-%d").to_string(),
+                    _ => "Never".to_owned(),
+                },
+            ),
+            None => ("-".to_owned(), "-".to_owned()),
+        };
+        println!(
+            "{:<20}{:<12}{:<9}{:<20}{:<24}{}",
+            provider.name.green(),
+            provider.vehicle_type.to_string(),
+            provider.proxies.len(),
+            updated,
+            quota,
+            expire
+        );
+        if with_proxies {
+            for proxy in &provider.proxies {
+                let delay = proxy
+                    .history
+                    .first()
+                    .map(|x| match x.delay {
+                        0 => "?".to_owned(),
+                        delay => delay.to_string(),
+                    })
+                    .unwrap_or_else(|| "-".into());
+                println!(
+                    "  {:<18}{:<8}{}",
+                    proxy.proxy_type.to_string().dimmed(),
+                    delay,
+                    proxy.name
+                )
+            }
+        }
+    }
+    println!("{:-<1$}", "", terminal_width as usize);
+}
+
+#[test]
+fn test_provider_run() {
+    use clashctl_core::mock::FakeClash;
+
+    let clash = FakeClash::new();
+    let flags = Flags::default();
+    let run = |args: &[&str]| {
+        #[derive(Parser)]
+        struct Cmd {
+            #[clap(subcommand)]
+            sub: ProviderSubcommand,
+        }
+        Cmd::parse_from([&["provider"], args].concat())
+            .sub
+            .run(&clash, &flags)
+    };
+
+    // The `Compatible` provider is skipped
+    run(&["update"]).unwrap();
+    run(&["healthcheck", "subscription"]).unwrap();
+    assert!(run(&["update", "Nowhere"]).is_err());
+    clash.fail("healthcheck_provider", 500);
+    assert!(run(&["healthcheck"]).is_err());
+    assert_eq!(
+        clash.calls(),
+        [
+            "get_providers",
+            "update_provider",
+            "get_providers",
+            "healthcheck_provider",
+            "get_provider",
+            "get_providers",
+            "get_providers",
+            "healthcheck_provider",
+            "healthcheck_provider"
+        ]
+    );
+}
@@ -1,14 +1,19 @@
-use std::time::Duration;
+use std::io::IsTerminal;
 
 use clap::{Parser, Subcommand};
-use clashctl_core::{model::ProxyType, strum::VariantNames};
-use log::{error, info, warn};
+use clashctl_core::{
+    model::{Proxy, ProxyType},
+    strum::VariantNames,
+    ClashApi,
+};
+use log::{error, info};
 use owo_colors::OwoColorize;
 use requestty::{prompt_one, Answer, ListItem, Question};
+use serde::Serialize;
 
 use crate::{
     interactive::{Flags, ProxySortBy, SortOrder},
-    RenderList, Result,
+    Error, ProxyTestOpt, RenderList, Result,
 };
 // use crate::{Result};
 
@@ -41,8 +46,27 @@ use crate::{
 pub enum ProxySubcommand {
     #[clap(alias = "ls", about = "List proxies (alias ls)")]
     List(ProxyListOpt),
-    #[clap(about = "Set active proxy")]
-    Use,
+    #[clap(about = "Set active proxy, prompt for what's omitted")]
+    Use(ProxyUseOpt),
+    #[clap(about = "Test latency of proxies")]
+    Test(ProxyTestOpt),
+}
+
+/// [`Proxy`] with its name, for structured output
+#[derive(Serialize, Debug)]
+struct NamedProxy<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    proxy: &'a Proxy,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ProxyUseOpt {
+    #[clap(help = "Selector group to change, matched case-insensitively and fuzzily")]
+    pub group: Option<String>,
+
+    #[clap(help = "Proxy to use, matched case-insensitively and fuzzily")]
+    pub proxy: Option<String>,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -88,41 +112,56 @@ pub struct ProxyListOpt {
 
 impl ProxySubcommand {
     pub fn handle(&self, flags: &Flags) -> Result<()> {
-        let config = flags.get_config()?;
-        let server = match config.using_server() {
-            Some(server) => server.to_owned(),
-            None => {
-                warn!("No server configured yet. Use `clashctl server add` first.");
-                return Ok(());
-            }
-        };
-        info!("Using {}", server);
-        let clash = server.into_clash_with_timeout(Some(Duration::from_millis(flags.timeout)))?;
+        self.run(&flags.connect_server_from_config()?, flags)
+    }
 
+    pub fn run<C: ClashApi>(&self, clash: &C, flags: &Flags) -> Result<()> {
         match self {
             ProxySubcommand::List(opt) => {
                 let proxies = clash.get_proxies()?;
-                proxies.render_list(opt);
+                let list = proxies
+                    .plain_list(opt)
+                    .into_iter()
+                    .map(|(name, proxy)| NamedProxy { name, proxy })
+                    .collect::<Vec<_>>();
+                flags.output.print_or(&list, || proxies.render_list(opt))?;
             }
-            ProxySubcommand::Use => {
+            ProxySubcommand::Test(opt) => opt.handle(clash, flags)?,
+            ProxySubcommand::Use(opt) => {
                 let proxies = clash.get_proxies()?;
-                let mut groups = proxies
-                    .iter()
-                    .filter(|(_, p)| p.proxy_type.is_selector())
-                    .map(|(name, _)| name)
-                    .filter(|name| !["GLOBAL", "REJECT"].contains(&name.as_str()))
-                    .collect::<Vec<_>>();
-                groups.sort();
-                let group_selected = match prompt_one(
-                    Question::select("proxy")
-                        .message("Which group to change?")
-                        .choices(groups)
-                        .build(),
-                ) {
-                    Ok(result) => result.as_list_item().unwrap().text.to_owned(),
-                    Err(e) => {
-                        error!("Error selecting proxy: {}", e);
-                        return Err(e.into());
+                let group_selected = match opt.group {
+                    Some(ref group) => {
+                        let groups = proxies.groups().map(|(name, _)| name.as_str());
+                        let group = resolve("group", group, groups)?;
+                        let proxy_type = proxies[group].proxy_type;
+                        if !proxy_type.is_selector() {
+                            return Err(Error::bad_option(format!(
+                                "Group {} is a {}, only Selector can be changed",
+                                group, proxy_type
+                            )));
+                        }
+                        group.to_owned()
+                    }
+                    None => {
+                        ensure_tty("group")?;
+                        let mut groups = proxies
+                            .selectors()
+                            .map(|(name, _)| name)
+                            .filter(|name| !["GLOBAL", "REJECT"].contains(&name.as_str()))
+                            .collect::<Vec<_>>();
+                        groups.sort();
+                        match prompt_one(
+                            Question::select("proxy")
+                                .message("Which group to change?")
+                                .choices(groups)
+                                .build(),
+                        ) {
+                            Ok(result) => result.as_list_item().unwrap().text.to_owned(),
+                            Err(e) => {
+                                error!("Error selecting proxy: {}", e);
+                                return Err(e.into());
+                            }
+                        }
                     }
                 };
                 let proxy = clash.get_proxy(&group_selected)?;
@@ -130,21 +169,29 @@ impl ProxySubcommand {
                 // all / now only occurs when proxy_type is [`ProxyType::Selector`]
                 let members = proxy.all.unwrap();
                 let now = proxy.now.unwrap();
-                let cur_index = members.iter().position(|x| x == &now).unwrap();
-                let mut question = Question::select("proxy")
-                    .message("Which proxy to use?")
-                    .choices(members);
-                if cur_index != 0 {
-                    question = question.default(cur_index)
-                }
-                let member_selected = match prompt_one(question.build()) {
-                    Ok(result) => match result {
-                        Answer::ListItem(ListItem { text, .. }) => text,
-                        _ => unreachable!(),
-                    },
-                    Err(e) => {
-                        error!("Error selecting proxy: {}", e);
-                        return Err(e.into());
+                let member_selected = match opt.proxy {
+                    Some(ref member) => {
+                        resolve("proxy", member, members.iter().map(String::as_str))?.to_owned()
+                    }
+                    None => {
+                        ensure_tty("proxy")?;
+                        let cur_index = members.iter().position(|x| x == &now).unwrap();
+                        let mut question = Question::select("proxy")
+                            .message("Which proxy to use?")
+                            .choices(members);
+                        if cur_index != 0 {
+                            question = question.default(cur_index)
+                        }
+                        match prompt_one(question.build()) {
+                            Ok(result) => match result {
+                                Answer::ListItem(ListItem { text, .. }) => text,
+                                _ => unreachable!(),
+                            },
+                            Err(e) => {
+                                error!("Error selecting proxy: {}", e);
+                                return Err(e.into());
+                            }
+                        }
                     }
                 };
                 info!(
@@ -160,6 +207,87 @@ impl ProxySubcommand {
     }
 }
 
+/// Prompting is only possible when stdin is a terminal, e.g. not in scripts
+pub(super) fn ensure_tty(what: &str) -> Result<()> {
+    if std::io::stdin().is_terminal() {
+        Ok(())
+    } else {
+        Err(Error::bad_option(format!(
+            "No {} given, and cannot prompt for it since stdin is not a TTY",
+            what
+        )))
+    }
+}
+
+/// Find the candidate `input` refers to. Exact match is preferred, then
+/// case-insensitive match, then case-insensitive substring, and finally
+/// case-insensitive subsequence (e.g. `hk1` for `HK-01`). Error if nothing or
+/// more than one candidate matches at the first level that matches at all.
+pub(super) fn resolve<'a>(
+    what: &str,
+    input: &str,
+    candidates: impl Iterator<Item = &'a str>,
+) -> Result<&'a str> {
+    let candidates = candidates.collect::<Vec<_>>();
+    let lower = input.to_lowercase();
+    let is_subsequence = |candidate: &str| {
+        let mut chars = candidate.chars().flat_map(char::to_lowercase);
+        lower.chars().all(|c| chars.any(|x| x == c))
+    };
+    let levels: [&dyn Fn(&str) -> bool; 4] = [
+        &|x| x == input,
+        &|x| x.to_lowercase() == lower,
+        &|x| x.to_lowercase().contains(&lower),
+        &is_subsequence,
+    ];
+
+    for level in levels {
+        let mut found = candidates
+            .iter()
+            .copied()
+            .filter(|x| level(x))
+            .collect::<Vec<_>>();
+        match found.len() {
+            0 => continue,
+            1 => return Ok(found[0]),
+            _ => {
+                found.sort_unstable();
+                return Err(Error::bad_option(format!(
+                    "{} `{}` is ambiguous, candidates are: {}",
+                    what,
+                    input,
+                    found.join(", ")
+                )));
+            }
+        }
+    }
+
+    let mut candidates = candidates;
+    candidates.sort_unstable();
+    Err(Error::bad_option(format!(
+        "No {} matches `{}`, candidates are: {}",
+        what,
+        input,
+        candidates.join(", ")
+    )))
+}
+
+#[test]
+fn test_resolve() {
+    let candidates = ["Proxy", "proxy-backup", "HK-01", "HK-02", "US-01"];
+    let resolve = |input| resolve("proxy", input, candidates.iter().copied());
+
+    assert_eq!(resolve("Proxy").unwrap(), "Proxy");
+    assert_eq!(resolve("us").unwrap(), "US-01");
+    assert_eq!(resolve("hk2").unwrap(), "HK-02");
+    assert_eq!(resolve("backup").unwrap(), "proxy-backup");
+    assert_eq!(
+        resolve("hk").unwrap_err().to_string(),
+        "proxy `hk` is ambiguous, candidates are: HK-01, HK-02"
+    );
+    assert!(resolve("jp").is_err());
+}
+
 #[test]
 fn test_proxy_type() {
     let string = "direct";
@@ -0,0 +1,227 @@
+use std::cmp::Ordering;
+
+use clap::Parser;
+use clashctl_core::{strum::VariantNames, ClashApi};
+use log::{debug, info, warn};
+use owo_colors::OwoColorize;
+use rayon::{prelude::*, ThreadPoolBuilder};
+use serde::Serialize;
+use terminal_size::{terminal_size, Height, Width};
+
+use super::proxy::resolve;
+use crate::{
+    interactive::{Flags, OrderBy, SortOrder},
+    Error, Result,
+};
+
+#[derive(Parser, Debug, Clone)]
+pub struct ProxyTestOpt {
+    #[clap(
+        help = "Proxies or groups to test, matched like in `proxy use`. Groups are expanded into \
+                their members. Test all proxies if omitted"
+    )]
+    pub targets: Vec<String>,
+
+    #[clap(short = 'j', long, default_value = "8")]
+    /// Number of proxies to test at the same time
+    pub concurrency: usize,
+
+    #[clap(short = 'n', long, default_value = "3")]
+    /// Times to test each proxy
+    pub rounds: usize,
+
+    #[clap(
+        long,
+        default_value = "avg",
+        possible_values = LatencySortBy::VARIANTS,
+    )]
+    pub sort_by: LatencySortBy,
+
+    #[clap(
+        long,
+        default_value = "ascendant",
+        possible_values = &["ascendant", "descendant"],
+    )]
+    pub sort_order: SortOrder,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::Display, strum::EnumVariantNames,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "lowercase")]
+pub enum LatencySortBy {
+    Name,
+    Min,
+    Avg,
+    P95,
+    Loss,
+}
+
+/// Latency of a proxy over several rounds of testing, in ms
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LatencyStats {
+    pub name: String,
+    pub min: Option<u64>,
+    pub avg: Option<u64>,
+    pub p95: Option<u64>,
+    /// Fraction of rounds that failed, from 0 to 1
+    pub loss: f64,
+    pub rounds: usize,
+}
+
+impl LatencyStats {
+    /// `samples` contains delay of each round, `None` for failed ones
+    pub fn from_samples(name: String, samples: &[Option<u64>]) -> Self {
+        let mut delays = samples.iter().flatten().copied().collect::<Vec<_>>();
+        delays.sort_unstable();
+        let count = delays.len();
+        let rounds = samples.len();
+
+        // Nearest-rank percentile
+        let p95 = (count * 95).div_ceil(100).checked_sub(1);
+
+        Self {
+            name,
+            min: delays.first().copied(),
+            avg: (count != 0).then(|| delays.iter().sum::<u64>() / count as u64),
+            p95: p95.map(|index| delays[index]),
+            loss: if rounds == 0 {
+                0.0
+            } else {
+                (rounds - count) as f64 / rounds as f64
+            },
+            rounds,
+        }
+    }
+
+    fn cmp_by(&self, other: &Self, by: LatencySortBy) -> Ordering {
+        // Proxies without any successful round go last
+        let key = |x: Option<u64>| x.unwrap_or(u64::MAX);
+        match by {
+            LatencySortBy::Name => self.name.cmp(&other.name),
+            LatencySortBy::Min => key(self.min).cmp(&key(other.min)),
+            LatencySortBy::Avg => key(self.avg).cmp(&key(other.avg)),
+            LatencySortBy::P95 => key(self.p95).cmp(&key(other.p95)),
+            LatencySortBy::Loss => self.loss.total_cmp(&other.loss),
+        }
+    }
+}
+
+impl ProxyTestOpt {
+    pub fn handle<C: ClashApi>(&self, clash: &C, flags: &Flags) -> Result<()> {
+        if self.concurrency == 0 || self.rounds == 0 {
+            return Err(Error::bad_option(
+                "Concurrency and rounds should be at least 1",
+            ));
+        }
+
+        let proxies = clash.get_proxies()?;
+        let is_normal = |name: &&str| proxies.get(*name).is_some_and(|x| x.proxy_type.is_normal());
+        let mut names = if self.targets.is_empty() {
+            proxies.normal().map(|(name, _)| name.as_str()).collect()
+        } else {
+            let mut names = vec![];
+            for target in &self.targets {
+                let name = resolve("proxy", target, proxies.keys().map(String::as_str))?;
+                let proxy = &proxies[name];
+                if proxy.proxy_type.is_group() {
+                    // all only occurs when proxy_type is a group
+                    names.extend(
+                        proxy
+                            .all
+                            .iter()
+                            .flatten()
+                            .map(String::as_str)
+                            .filter(is_normal),
+                    )
+                } else if proxy.proxy_type.is_normal() {
+                    names.push(name)
+                } else {
+                    warn!("{} is a {}, skipped", name, proxy.proxy_type)
+                }
+            }
+            names
+        };
+        names.sort_unstable();
+        names.dedup();
+
+        info!(
+            "Testing {} proxies for {} round(s) with {}",
+            names.len(),
+            self.rounds,
+            flags.test_url()
+        );
+
+        let (test_url, timeout) = (flags.test_url(), flags.timeout());
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(self.concurrency)
+            .build()
+            .map_err(|e| Error::bad_option(e.to_string()))?;
+        let mut stats = pool.install(|| {
+            names
+                .par_iter()
+                .map(|name| {
+                    let samples = (0..self.rounds)
+                        .map(
+                            |_| match clash.get_proxy_delay(name, test_url.as_str(), timeout) {
+                                Ok(delay) if delay.delay > 0 => Some(delay.delay),
+                                Ok(_) => None,
+                                Err(e) => {
+                                    debug!("Failed to test {}: {}", name, e);
+                                    None
+                                }
+                            },
+                        )
+                        .collect::<Vec<_>>();
+                    LatencyStats::from_samples(name.to_string(), &samples)
+                })
+                .collect::<Vec<_>>()
+        });
+        stats.sort_by(|a, b| a.cmp_by(b, self.sort_by).order_by(self.sort_order));
+
+        flags.output.print_or(&stats, || {
+            let (Width(terminal_width), _) = terminal_size().unwrap_or((Width(70), Height(0)));
+            let ms = |x: Option<u64>| x.map_or_else(|| "-".to_owned(), |x| format!("{}ms", x));
+
+            println!("\n{:-<1$}", "", terminal_width as usize);
+            println!("{:<40}{:<10}{:<10}{:<10}LOSS", "NAME", "MIN", "AVG", "P95");
+            println!("{:-<1$}", "", terminal_width as usize);
+            for stat in &stats {
+                let avg = format!("{:<10}", ms(stat.avg));
+                println!(
+                    "{:<40}{:<10}{}{:<10}{:.0}%",
+                    stat.name,
+                    ms(stat.min),
+                    match stat.avg {
+                        Some(0..=200) => avg.green().to_string(),
+                        Some(201..=400) => avg.yellow().to_string(),
+                        _ => avg.red().to_string(),
+                    },
+                    ms(stat.p95),
+                    stat.loss * 100.0
+                );
+            }
+            println!("{:-<1$}", "", terminal_width as usize);
+        })
+    }
+}
+
+#[test]
+fn test_latency_stats() {
+    let samples = [Some(120), None, Some(80), Some(100)];
+    let stats = LatencyStats::from_samples("HK-01".to_owned(), &samples);
+    assert_eq!(stats.min, Some(80));
+    assert_eq!(stats.avg, Some(100));
+    assert_eq!(stats.p95, Some(120));
+    assert_eq!(stats.loss, 0.25);
+    assert_eq!(stats.rounds, 4);
+
+    let failed = LatencyStats::from_samples("US-01".to_owned(), &[None, None]);
+    assert_eq!((failed.min, failed.avg, failed.p95), (None, None, None));
+    assert_eq!(failed.loss, 1.0);
+    assert_eq!(
+        stats.cmp_by(&failed, LatencySortBy::Avg),
+        Ordering::Less,
+        "Proxies without successful round should go last"
+    );
+}
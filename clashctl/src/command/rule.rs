@@ -0,0 +1,263 @@
+use std::{cmp::Reverse, collections::BTreeMap, net::IpAddr};
+
+use clap::{Parser, Subcommand};
+use clashctl_core::{
+    model::{Rule, RuleType, Rules},
+    strum::VariantNames,
+    ClashApi, IndexedRule, MatchTarget,
+};
+use owo_colors::OwoColorize;
+use regex::Regex;
+use serde::Serialize;
+use terminal_size::{terminal_size, Height, Width};
+
+use crate::{
+    interactive::{Flags, RuleSort, RuleSortBy, SortMethod, SortOrder},
+    Result,
+};
+
+#[derive(Subcommand, Debug)]
+#[clap(about = "Interacting with rules")]
+pub enum RuleSubcommand {
+    #[clap(alias = "ls", about = "List rules (alias ls)")]
+    List(RuleListOpt),
+    #[clap(about = "Find which rule and proxy a host or IP would hit")]
+    Match(RuleMatchOpt),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RuleListOpt {
+    #[clap(
+        short = 't',
+        long = "type",
+        help = "Only rules of these types",
+        possible_values = RuleType::VARIANTS,
+        ignore_case = true
+    )]
+    pub types: Vec<RuleType>,
+
+    #[clap(
+        short,
+        long,
+        help = "Only rules going to this proxy or group, case-insensitive"
+    )]
+    pub proxy: Option<String>,
+
+    #[clap(
+        long,
+        help = "Only rules whose payload contains this, case-insensitive"
+    )]
+    pub payload: Option<String>,
+
+    #[clap(long, help = "Only rules whose payload matches this regex")]
+    pub regex: Option<Regex>,
+
+    #[clap(
+        long,
+        possible_values = &["payload", "proxy", "type"],
+        help = "Sort rules. Keep the order clash evaluates them in if omitted"
+    )]
+    pub sort_by: Option<RuleSortBy>,
+
+    #[clap(
+        long,
+        default_value = "ascendant",
+        possible_values = &["ascendant", "descendant"],
+    )]
+    pub sort_order: SortOrder,
+
+    #[clap(short, long, help = "Group rules by the proxy they go to, with counts")]
+    pub group: bool,
+}
+
+/// Rules going to the same proxy
+#[derive(Serialize, Debug)]
+struct RuleGroup<'a> {
+    proxy: &'a str,
+    count: usize,
+    rules: Vec<IndexedRule<'a>>,
+}
+
+impl RuleListOpt {
+    pub fn matches(&self, rule: &Rule) -> bool {
+        (self.types.is_empty() || self.types.contains(&rule.rule_type))
+            && self
+                .proxy
+                .as_ref()
+                .is_none_or(|proxy| rule.proxy.eq_ignore_ascii_case(proxy))
+            && self.payload.as_ref().is_none_or(|payload| {
+                rule.payload
+                    .to_lowercase()
+                    .contains(&payload.to_lowercase())
+            })
+            && self
+                .regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&rule.payload))
+    }
+
+    /// Filtered and sorted rules, with their index in the original list
+    pub fn list<'a>(&self, rules: &'a Rules) -> Vec<IndexedRule<'a>> {
+        let mut list = rules
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| self.matches(rule))
+            .map(|(index, rule)| IndexedRule { index, rule })
+            .collect::<Vec<_>>();
+        if let Some(by) = self.sort_by {
+            let sort = RuleSort::new(by, self.sort_order);
+            list.sort_by(|a, b| sort.sort_fn(a.rule, b.rule));
+        }
+        list
+    }
+
+    pub fn handle(&self, rules: &Rules, flags: &Flags) -> Result<()> {
+        let list = self.list(rules);
+        if !self.group {
+            return flags.output.print_or(&list, || {
+                print_table(list.iter().map(|x| (x.index, x.rule)))
+            });
+        }
+
+        let mut groups = BTreeMap::<&str, Vec<IndexedRule>>::new();
+        for rule in list {
+            groups.entry(&rule.rule.proxy).or_default().push(rule)
+        }
+        // Largest groups go first. On a tie, built-in proxies (DIRECT & REJECT)
+        // go last
+        let mut groups = groups
+            .into_iter()
+            .map(|(proxy, rules)| RuleGroup {
+                proxy,
+                count: rules.len(),
+                rules,
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by_key(|x| (Reverse(x.count), matches!(x.proxy, "DIRECT" | "REJECT")));
+
+        flags.output.print_or(&groups, || {
+            for group in &groups {
+                println!("\n{} ({})", group.proxy.green(), group.count);
+                print_table(group.rules.iter().map(|x| (x.index, x.rule)));
+            }
+        })
+    }
+}
+
+fn print_table<'a>(rules: impl Iterator<Item = (usize, &'a Rule)>) {
+    let (Width(terminal_width), _) = terminal_size().unwrap_or((Width(70), Height(0)));
+    println!("\n{:-<1$}", "", terminal_width as usize);
+    println!("{:<8}{:<16}{:<40}PROXY", "INDEX", "TYPE", "PAYLOAD");
+    println!("{:-<1$}", "", terminal_width as usize);
+    for (index, rule) in rules {
+        println!(
+            "{:<8}{:<16}{:<40}{}",
+            index,
+            rule.rule_type.to_string(),
+            rule.payload,
+            rule.proxy.green()
+        );
+    }
+    println!("{:-<1$}", "", terminal_width as usize);
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RuleMatchOpt {
+    #[clap(help = "Domain or IP, with optional port, e.g. google.com:443 or 1.1.1.1")]
+    pub target: String,
+
+    #[clap(short, long, help = "Destination port, overrides the one in target")]
+    pub port: Option<u16>,
+
+    #[clap(long, help = "Source IP of the connection")]
+    pub src: Option<IpAddr>,
+
+    #[clap(long, help = "Source port of the connection")]
+    pub src_port: Option<u16>,
+
+    #[clap(long, help = "Name of the process making the connection")]
+    pub process: Option<String>,
+}
+
+impl RuleMatchOpt {
+    pub fn target(&self) -> MatchTarget {
+        let mut target = MatchTarget::parse(&self.target);
+        target.dst_port = self.port.or(target.dst_port);
+        target.src_ip = self.src;
+        target.src_port = self.src_port;
+        target.process = self.process.clone();
+        target
+    }
+}
+
+impl RuleSubcommand {
+    pub fn handle(&self, flags: &Flags) -> Result<()> {
+        self.run(&flags.connect_server_from_config()?, flags)
+    }
+
+    pub fn run<C: ClashApi>(&self, clash: &C, flags: &Flags) -> Result<()> {
+        match self {
+            Self::List(opt) => opt.handle(&clash.get_rules()?, flags),
+            Self::Match(opt) => {
+                let rules = clash.get_rules()?;
+                let result = rules.find_match(&opt.target());
+                flags.output.print_or(&result, || {
+                    for unknown in &result.unknown {
+                        let rule = unknown.rule.rule;
+                        println!(
+                            "{} #{} {},{} → {} ({})",
+                            "May match".yellow(),
+                            unknown.rule.index,
+                            rule.rule_type,
+                            rule.payload,
+                            rule.proxy,
+                            unknown.reason
+                        );
+                    }
+                    match result.matched {
+                        Some(ref matched) => {
+                            let rule = matched.rule;
+                            println!(
+                                "{} #{} {},{} → {}",
+                                "Matched".green(),
+                                matched.index,
+                                rule.rule_type,
+                                rule.payload,
+                                rule.proxy.green()
+                            )
+                        }
+                        None => println!("{}", "No rule matched".red()),
+                    }
+                })
+            }
+        }
+    }
+}
+
+#[test]
+fn test_rule_list() {
+    let rule = |rule_type, payload: &str, proxy: &str| Rule {
+        rule_type,
+        payload: payload.to_owned(),
+        proxy: proxy.to_owned(),
+    };
+    let rules = Rules {
+        rules: vec![
+            rule(RuleType::DomainSuffix, "google.com", "Proxy"),
+            rule(RuleType::DomainKeyword, "google", "Proxy"),
+            rule(RuleType::DomainSuffix, "baidu.com", "DIRECT"),
+            rule(RuleType::IPCIDR, "10.0.0.0/8", "DIRECT"),
+        ],
+    };
+    let indexes = |args: &[&str]| {
+        let opt = RuleListOpt::parse_from([&["list"], args].concat());
+        opt.list(&rules).iter().map(|x| x.index).collect::<Vec<_>>()
+    };
+
+    assert_eq!(indexes(&[]), vec![0, 1, 2, 3]);
+    assert_eq!(indexes(&["-t", "domainsuffix"]), vec![0, 2]);
+    assert_eq!(indexes(&["-p", "direct", "--payload", "BAIDU"]), vec![2]);
+    assert_eq!(indexes(&["--regex", r"^\w+\.com$"]), vec![0, 2]);
+    assert_eq!(indexes(&["--sort-by", "payload"]), vec![3, 2, 1, 0]);
+}
@@ -1,28 +1,159 @@
-use clap::Subcommand;
+use std::env;
+
+use clap::{Parser, Subcommand};
 use log::{debug, info, warn};
 use owo_colors::OwoColorize;
 use requestty::{prompt, prompt_one, Answers, Question};
+use serde::Serialize;
 use terminal_size::{terminal_size, Height, Width};
 use url::Url;
 
+use super::proxy::ensure_tty;
 use crate::{
-    interactive::{Flags, Server},
-    Result,
+    interactive::{ConfigData, Flags, Server},
+    Error, Result,
 };
 
 // use crate::Result;
 
+/// Server as listed in structured output, without its secret
+#[derive(Serialize, Debug)]
+struct ServerEntry<'a> {
+    name: Option<&'a str>,
+    url: &'a str,
+    active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    test_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_path: Option<&'a str>,
+}
+
 #[derive(Subcommand, Debug)]
 #[clap(about = "Interacting with servers")]
 pub enum ServerSubcommand {
-    #[clap(alias = "a", about = "Add new server (alias a)")]
-    Add,
+    #[clap(
+        alias = "a",
+        about = "Add new server (alias a). Prompt for it if url is omitted"
+    )]
+    Add(ServerAddOpt),
     #[clap(about = "Select active server")]
-    Use,
+    Use {
+        #[clap(help = "Name or url of server. Prompt for it if omitted")]
+        server: Option<String>,
+    },
     #[clap(alias = "ls", about = "Show current active server")]
     List,
     #[clap(about = "Remove servers")]
-    Del,
+    Del {
+        #[clap(help = "Names or urls of servers. Prompt for them if omitted")]
+        servers: Vec<String>,
+    },
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ServerAddOpt {
+    #[clap(short, long, help = "Name to refer to the server")]
+    pub name: Option<String>,
+
+    #[clap(short, long, help = "URL of Clash API")]
+    pub url: Option<Url>,
+
+    #[clap(
+        long,
+        requires = "url",
+        help = "Environment variable holding secret of Clash API"
+    )]
+    pub secret_env: Option<String>,
+
+    #[clap(
+        long,
+        requires = "url",
+        help = "Url for testing proxies with this server"
+    )]
+    pub test_url: Option<Url>,
+
+    #[clap(
+        long,
+        requires = "url",
+        help = "Timeout of requests to this server, in ms"
+    )]
+    pub timeout: Option<u64>,
+
+    #[clap(
+        long,
+        requires = "url",
+        help = "Path of config file on the host of Clash"
+    )]
+    pub config_path: Option<String>,
+}
+
+impl ServerAddOpt {
+    /// Build the server from options, or from prompts if url is omitted
+    pub fn server(&self) -> Result<Server> {
+        let url = match self.url {
+            Some(ref url) => url.clone(),
+            None => return self.prompt(),
+        };
+        let secret = match self.secret_env {
+            Some(ref var) => Some(env::var(var).map_err(|e| {
+                Error::bad_option(format!("Cannot read secret from ${}: {}", var, e))
+            })?),
+            None => None,
+        };
+        Ok(Server {
+            url,
+            secret,
+            name: self.name.clone(),
+            test_url: self.test_url.clone(),
+            timeout: self.timeout,
+            config_path: self.config_path.clone(),
+        })
+    }
+
+    fn prompt(&self) -> Result<Server> {
+        ensure_tty("url")?;
+        let questions = [
+            Question::input("url")
+                .message("URL of Clash API")
+                .validate(|input, _| match Url::parse(input) {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(format!("Invalid URL: {}", e)),
+                })
+                .build(),
+            Question::input("name")
+                .message("Name of the server, default to None:")
+                .when(|_: &Answers| self.name.is_none())
+                .build(),
+            Question::password("secret")
+                .message("Secret of Clash API, default to None:")
+                .build(),
+            Question::input("config_path")
+                .message("Path of config file on the host of Clash, default to None:")
+                .build(),
+        ];
+        let mut res = prompt(questions).expect("Error during prompt");
+        debug!("{:#?}", res);
+        let mut optional = |key: &str| match res.remove(key).and_then(|x| x.try_into_string().ok())
+        {
+            Some(string) if string.is_empty() => None,
+            string => string,
+        };
+        let secret = optional("secret");
+        let config_path = optional("config_path");
+        let name = optional("name").or_else(|| self.name.clone());
+        let url = Url::parse(&optional("url").unwrap()).unwrap();
+
+        Ok(Server {
+            url,
+            secret,
+            name,
+            test_url: None,
+            timeout: None,
+            config_path,
+        })
+    }
 }
 
 impl ServerSubcommand {
@@ -30,90 +161,108 @@ impl ServerSubcommand {
         let mut config = flags.get_config()?;
 
         match self {
-            Self::Add => {
-                let questions = [
-                    Question::input("url")
-                        .message("URL of Clash API")
-                        .validate(|input, _| match Url::parse(input) {
-                            Ok(_) => Ok(()),
-                            Err(e) => Err(format!("Invalid URL: {}", e)),
-                        })
-                        .build(),
-                    Question::password("secret")
-                        .message("Secret of Clash API, default to None:")
-                        .build(),
-                ];
-                let mut res = prompt(questions).expect("Error during prompt");
-                debug!("{:#?}", res);
-                let secret = match res.remove("secret").unwrap().try_into_string().unwrap() {
-                    string if string == *"" => None,
-                    secret => Some(secret),
-                };
-
-                let url_str = res.remove("url").unwrap().try_into_string().unwrap();
-                let url = Url::parse(&url_str).unwrap();
-
-                let server = Server {
-                    secret,
-                    url: url.clone(),
-                };
+            Self::Add(opt) => {
+                let server = opt.server()?;
+                if let Some(ref name) = server.name {
+                    if config.find_server(name).is_some() {
+                        return Err(Error::bad_option(format!(
+                            "Server named {} already exists",
+                            name
+                        )));
+                    }
+                }
 
                 info!("Adding {}", server);
 
                 config.servers.push(server);
                 debug!("{:#?}", config.servers);
-                config.use_server(url)?;
+                config.use_server(config.servers.len() - 1)?;
                 config.write()?;
             }
-            Self::Use => {
+            Self::Use { server } => {
                 if config.servers.is_empty() {
                     warn!("No server configured yet. Use `clashctl server add` first.");
                     return Ok(());
                 }
-                let servers = config.servers.iter().map(|server| server.url.as_str());
-                let ans = &prompt_one(
-                    Question::select("server")
-                        .message("Select active server to interact with")
-                        .choices(servers)
-                        .build(),
-                )?;
-                let ans_str = &ans.as_list_item().unwrap().text;
-                config.use_server(Url::parse(ans_str).unwrap())?;
+                let index = match server {
+                    Some(server) => find(&config, server)?,
+                    None => {
+                        ensure_tty("server")?;
+                        let servers = config.servers.iter().map(ToString::to_string);
+                        let ans = &prompt_one(
+                            Question::select("server")
+                                .message("Select active server to interact with")
+                                .choices(servers)
+                                .build(),
+                        )?;
+                        ans.as_list_item().unwrap().index
+                    }
+                };
+                config.use_server(index)?;
                 config.write()?;
             }
             Self::List => {
-                if config.servers.is_empty() {
+                if config.servers.is_empty() && flags.output.is_table() {
                     warn!("No server configured yet. Use `clashctl server add` first.");
                     return Ok(());
                 }
-                let (Width(terminal_width), _) = terminal_size().unwrap_or((Width(70), Height(0)));
-                let active = config.using_server();
-                println!("\n{:-<1$}", "", terminal_width as usize);
-                println!("{:<8}{:<50}", "ACTIVE".green(), "URL");
-                println!("{:-<1$}", "", terminal_width as usize);
-                for server in &config.servers {
-                    let is_active = match active {
-                        Some(active) => server == active,
-                        _ => false,
-                    };
-                    println!(
-                        "{:^8}{:<50}",
-                        if is_active { "→".green() } else { "".green() },
-                        server.url.as_str(),
-                    )
-                }
-                println!("{:-<1$}\n", "", terminal_width as usize);
+                let list = config
+                    .servers
+                    .iter()
+                    .enumerate()
+                    .map(|(index, server)| ServerEntry {
+                        name: server.name.as_deref(),
+                        url: server.url.as_str(),
+                        active: config.active == Some(index),
+                        test_url: server.test_url.as_ref().map(Url::as_str),
+                        timeout: server.timeout,
+                        config_path: server.config_path.as_deref(),
+                    })
+                    .collect::<Vec<_>>();
+                flags.output.print_or(&list, || {
+                    let (Width(terminal_width), _) =
+                        terminal_size().unwrap_or((Width(70), Height(0)));
+                    println!("\n{:-<1$}", "", terminal_width as usize);
+                    println!("{:<8}{:<20}{:<50}", "ACTIVE".green(), "NAME", "URL");
+                    println!("{:-<1$}", "", terminal_width as usize);
+                    for server in &list {
+                        println!(
+                            "{:^8}{:<20}{:<50}",
+                            if server.active {
+                                "→".green()
+                            } else {
+                                "".green()
+                            },
+                            server.name.unwrap_or("-"),
+                            server.url,
+                        )
+                    }
+                    println!("{:-<1$}\n", "", terminal_width as usize);
+                })?;
             }
-            Self::Del => {
+            Self::Del { servers } => {
                 if config.servers.is_empty() {
                     warn!("No server configured yet. Use `clashctl server add` first.");
                     return Ok(());
                 }
-                let servers = config.servers.iter().map(|server| server.url.as_str());
+                if !servers.is_empty() {
+                    let mut indices = servers
+                        .iter()
+                        .map(|server| find(&config, server))
+                        .collect::<Result<Vec<_>>>()?;
+                    indices.sort_unstable();
+                    indices.dedup();
+                    info!("Removing {} servers", indices.len());
+                    config.remove_servers(&indices);
+                    config.write()?;
+                    return Ok(());
+                }
+                ensure_tty("server")?;
+                let choices = config.servers.iter().map(ToString::to_string);
                 let ans = &prompt([
                     Question::multi_select("server")
                         .message("Select server(s) to remove")
-                        .choices(servers)
+                        .choices(choices)
                         .build(),
                     Question::confirm("confirm")
                         .when(|prev: &Answers| {
@@ -143,11 +292,8 @@ impl ServerSubcommand {
                     }
                     (Some(servers), Some(true)) => {
                         info!("Removing {} servers", servers.len());
-                        let server_names =
-                            servers.iter().map(|x| x.text.clone()).collect::<Vec<_>>();
-                        config
-                            .servers
-                            .retain(|f| !server_names.contains(&f.url.to_string()))
+                        let indices = servers.iter().map(|x| x.index).collect::<Vec<_>>();
+                        config.remove_servers(&indices)
                     }
                 }
                 debug!("{:#?}", config.servers);
@@ -157,3 +303,95 @@ impl ServerSubcommand {
         Ok(())
     }
 }
+
+/// Index of server referred to by `server`, either its name or url
+fn find(config: &ConfigData, server: &str) -> Result<usize> {
+    config
+        .find_server_index(server)
+        .ok_or_else(|| Error::bad_option(format!("Cannot find server {}", server)))
+}
+
+#[test]
+fn test_server_add_opt() {
+    env::set_var("CLASHCTL_TEST_SECRET", "secret");
+    let opt = ServerAddOpt::parse_from([
+        "add",
+        "--name",
+        "home",
+        "--url",
+        "http://127.0.0.1:9090",
+        "--secret-env",
+        "CLASHCTL_TEST_SECRET",
+        "--timeout",
+        "500",
+    ]);
+    let server = opt.server().unwrap();
+    assert_eq!(server.name.as_deref(), Some("home"));
+    assert_eq!(server.secret.as_deref(), Some("secret"));
+    assert_eq!(server.timeout, Some(500));
+    assert_eq!(server.test_url, None);
+
+    let mut config = ConfigData::default();
+    config.servers.push(server);
+    assert!(find(&config, "home").is_ok());
+    assert!(find(&config, "http://127.0.0.1:9090").is_ok());
+    assert!(find(&config, "office").is_err());
+
+    let opt = ServerAddOpt::parse_from([
+        "add",
+        "--url",
+        "http://127.0.0.1:9090",
+        "--secret-env",
+        "CLASHCTL_TEST_UNSET",
+    ]);
+    assert!(opt.server().is_err());
+}
+
+#[test]
+fn test_server_profiles() {
+    let path = env::temp_dir().join("clashctl_test_server_profiles.ron");
+    // Active server was kept by url before profiles
+    std::fs::write(
+        &path,
+        r#"(
+            servers: [
+                (url: "http://127.0.0.1:9090/", secret: None, name: Some("a")),
+                (url: "http://127.0.0.1:9090/", secret: Some("b"), name: Some("b")),
+                (url: "http://127.0.0.1:9091/", secret: None),
+            ],
+            using: Some("http://127.0.0.1:9090/"),
+        )"#,
+    )
+    .unwrap();
+    let flags = Flags {
+        config_path: Some(path.clone()),
+        ..Flags::default()
+    };
+    let run = |args: &[&str]| {
+        #[derive(Parser)]
+        struct Cmd {
+            #[clap(subcommand)]
+            sub: ServerSubcommand,
+        }
+        Cmd::parse_from([&["server"], args].concat())
+            .sub
+            .handle(&flags)
+            .unwrap();
+        flags.get_config().unwrap().get_inner().clone()
+    };
+    let active = |config: &ConfigData| config.using_server().and_then(|x| x.name.clone());
+
+    assert_eq!(active(&flags.get_config().unwrap()), Some("a".to_owned()));
+    assert_eq!(active(&run(&["use", "b"])), Some("b".to_owned()));
+
+    // Only `a` is removed though `b` shares its url
+    let config = run(&["del", "a"]);
+    assert_eq!(config.servers.len(), 2);
+    assert_eq!(active(&config), Some("b".to_owned()));
+
+    let config = run(&["del", "b"]);
+    assert_eq!(config.servers.len(), 1);
+    assert_eq!(config.using_server(), None);
+
+    std::fs::remove_file(path).unwrap();
+}
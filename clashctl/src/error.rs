@@ -1,3 +1,5 @@
+use clashctl_core::ErrorKind as CoreErrorKind;
+
 #[derive(Debug, thiserror::Error)]
 pub enum ErrorKind {
     #[error("{0}")]
@@ -11,11 +13,58 @@ pub enum ErrorKind {
 
     #[error("Requestty error")]
     RequesttyError(#[from] requestty::ErrorKind),
+
+    #[error("Failed to serialize output ({0})")]
+    SerializeError(#[from] serde_json::Error),
+
+    #[error("Failed to serialize output ({0})")]
+    SerializeYamlError(#[from] serde_yaml::Error),
+
+    #[error("{0}")]
+    BadOption(String),
 }
+
 #[derive(Debug, thiserror::Error)]
 #[error(transparent)]
 pub struct Error(Box<ErrorKind>);
 
+impl Error {
+    pub fn bad_option<S: Into<String>>(msg: S) -> Self {
+        Error(Box::new(ErrorKind::BadOption(msg.into())))
+    }
+
+    /// What the user may do to fix this, if known
+    pub fn hint(&self) -> Option<&'static str> {
+        use crate::{interactive::InteractiveError, ui::TuiError};
+
+        let core = match &*self.0 {
+            ErrorKind::ClashCtl(e)
+            | ErrorKind::InteractiveError(InteractiveError::ClashCtl(e))
+            | ErrorKind::TuiError(
+                TuiError::ClashCtl(e) | TuiError::InteractiveError(InteractiveError::ClashCtl(e)),
+            ) => e,
+            ErrorKind::InteractiveError(InteractiveError::ServerNotFound)
+            | ErrorKind::TuiError(TuiError::InteractiveError(InteractiveError::ServerNotFound)) => {
+                return Some("Add one with `clashctl server add`, or pick one with `server use`");
+            }
+            _ => return None,
+        };
+        match core.kind() {
+            CoreErrorKind::Unauthorized(_) => {
+                Some("Check the secret, or add the server again with `server add --secret-env`")
+            }
+            CoreErrorKind::ConnectionRefused(_) => {
+                Some("Check that clash is running and listening on the server url")
+            }
+            CoreErrorKind::Timeout => {
+                Some("Check the server url, or raise timeout with `--timeout <MS>`")
+            }
+            CoreErrorKind::NotFound(_) => Some("Check the name, e.g. with `clashctl proxy list`"),
+            _ => None,
+        }
+    }
+}
+
 impl<E> From<E> for Error
 where
     ErrorKind: From<E>,
@@ -26,3 +75,18 @@ where
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[test]
+fn test_hint() {
+    use clashctl_core::Error as CoreError;
+
+    let hint = |e: CoreError| Error::from(e).hint();
+    assert!(hint(CoreError::from_response(401, "")).is_some_and(|x| x.contains("--secret-env")));
+    assert!(hint(CoreError::timeout()).is_some_and(|x| x.contains("--timeout")));
+    assert_eq!(hint(CoreError::from_response(500, "")), None);
+    assert!(
+        Error::from(crate::interactive::InteractiveError::ServerNotFound)
+            .hint()
+            .is_some()
+    );
+}
@@ -19,6 +19,19 @@ use super::{ConfigData, InteractiveError, InteractiveResult};
 pub struct Server {
     pub url: url::Url,
     pub secret: Option<String>,
+    /// Name to refer to the server, instead of its url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Url for testing latency of proxies, overrides the default one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_url: Option<Url>,
+    /// Timeout of requests in ms, overrides the default one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Absolute path of config file on the host of clash, used by `config
+    /// reload` when no path is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_path: Option<String>,
 }
 
 impl Server {
@@ -37,7 +50,10 @@ impl Server {
 
 impl Display for Server {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Server ({})", self.url)
+        match self.name {
+            Some(ref name) => write!(f, "Server {} ({})", name, self.url),
+            None => write!(f, "Server ({})", self.url),
+        }
     }
 }
 
@@ -94,7 +110,8 @@ impl Config {
 
             debug!("Raw config:\n{}", buf);
 
-            let inner = from_str(&buf)?;
+            let mut inner: ConfigData = from_str(&buf)?;
+            inner.migrate();
 
             drop(file);
 
@@ -126,17 +143,11 @@ impl Config {
         Ok(())
     }
 
-    pub fn using_server(&self) -> Option<&Server> {
-        match self.using {
-            Some(ref using) => self.servers.iter().find(|x| &x.url == using),
-            None => None,
-        }
-    }
-
-    pub fn use_server(&mut self, url: Url) -> InteractiveResult<()> {
-        match self.get_server(&url) {
+    /// Make the server at `index` of `servers` active
+    pub fn use_server(&mut self, index: usize) -> InteractiveResult<()> {
+        match self.servers.get(index) {
             Some(_s) => {
-                self.using = Some(url);
+                self.active = Some(index);
                 Ok(())
             }
             None => Err(InteractiveError::ServerNotFound),
@@ -168,8 +179,6 @@ impl DerefMut for Config {
 
 #[test]
 fn test_config() {
-    use std::env;
-
     pretty_env_logger::formatted_builder()
         .filter_level(log::LevelFilter::Debug)
         .init();
@@ -177,8 +186,14 @@ fn test_config() {
     let mut config = Config::from_dir("/tmp/test.ron").unwrap();
     config.write().unwrap();
     config.servers.push(Server {
-        url: url::Url::parse(&env::var("PROXY_ADDR").unwrap()).unwrap(),
+        url: url::Url::parse("http://127.0.0.1:9090").unwrap(),
         secret: None,
+        name: Some("local".to_owned()),
+        test_url: None,
+        timeout: None,
+        config_path: None,
     });
+    assert!(config.find_server("local").is_some());
+    assert!(config.find_server("http://127.0.0.1:9090").is_some());
     config.write().unwrap();
 }
@@ -8,6 +8,12 @@ use crate::{ConSort, ProxySort, RuleSort, Server};
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ConfigData {
     pub servers: Vec<Server>,
+    /// Index of the active server in `servers`
+    #[serde(default)]
+    pub active: Option<usize>,
+    /// Url of the active server, kept by older versions. Only read to migrate
+    /// to `active`
+    #[serde(default, skip_serializing)]
     pub using: Option<Url>,
     #[serde(default)]
     pub tui: TuiConfig,
@@ -15,6 +21,51 @@ pub struct ConfigData {
     pub sort: SortsConfig,
 }
 
+impl ConfigData {
+    /// Find server by name, or by url if no name matches
+    pub fn find_server(&self, server: &str) -> Option<&Server> {
+        self.find_server_index(server)
+            .map(|index| &self.servers[index])
+    }
+
+    /// Index of server found by [`ConfigData::find_server`]
+    pub fn find_server_index(&self, server: &str) -> Option<usize> {
+        self.servers
+            .iter()
+            .position(|x| x.name.as_deref() == Some(server))
+            .or_else(|| {
+                let url = Url::parse(server).ok()?;
+                self.servers.iter().position(|x| x.url == url)
+            })
+    }
+
+    pub fn using_server(&self) -> Option<&Server> {
+        self.servers.get(self.active?)
+    }
+
+    /// Remove servers at `indices`, keeping the active one if it's not removed
+    pub fn remove_servers(&mut self, indices: &[usize]) {
+        self.active = self
+            .active
+            .filter(|active| !indices.contains(active))
+            .map(|active| active - (0..active).filter(|x| indices.contains(x)).count());
+        let mut index = 0;
+        self.servers.retain(|_| {
+            index += 1;
+            !indices.contains(&(index - 1))
+        });
+    }
+
+    /// Turn `using` of older versions into `active`
+    pub(crate) fn migrate(&mut self) {
+        if let Some(url) = self.using.take() {
+            if self.active.is_none() {
+                self.active = self.servers.iter().position(|x| x.url == url);
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct TuiConfig {
     pub log_file: Option<PathBuf>,
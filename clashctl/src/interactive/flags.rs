@@ -1,14 +1,16 @@
 use std::{path::PathBuf, time::Duration};
 
 use clap::Parser;
-use clashctl_core::Clash;
+use clashctl_core::{Clash, LongHaulTransport};
 use home::home_dir;
 use log::debug;
 use url::Url;
 
-use super::{Config, InteractiveError, InteractiveResult};
+use super::{Config, InteractiveError, InteractiveResult, Server};
+use crate::OutputFormat;
 
 const DEFAULT_TEST_URL: &str = "http://www.gstatic.com/generate_204";
+const DEFAULT_TIMEOUT: u64 = 2000;
 
 #[derive(Clone, Debug, Parser)]
 pub struct Flags {
@@ -16,9 +18,9 @@ pub struct Flags {
     /// Verbosity. Default: INFO, -v DEBUG, -vv TRACE
     pub verbose: u8,
 
-    #[clap(short, long, default_value = "2000")]
-    /// Timeout of requests, in ms
-    pub timeout: u64,
+    #[clap(short, long)]
+    /// Timeout of requests, in ms. Default to timeout of the server, or 2000
+    pub timeout: Option<u64>,
 
     #[clap(long, conflicts_with = "config-path")]
     /// Path of config directory. Default to ~/.config/clashctl
@@ -28,23 +30,41 @@ pub struct Flags {
     /// Path of config file. Default to ~/.config/clashctl/config.ron
     pub config_path: Option<PathBuf>,
 
+    #[clap(long)]
+    /// Url for testing proxy endpointes. Default to test url of the server, or
+    /// http://www.gstatic.com/generate_204
+    pub test_url: Option<Url>,
+
+    #[clap(long, global = true)]
+    /// Name or url of server to use, instead of the active one
+    pub server: Option<String>,
+
+    #[clap(long)]
+    /// Use WebSocket instead of chunked HTTP for traffic, logs and connections
+    pub websocket: bool,
+
     #[clap(
-            long,
-            default_value = DEFAULT_TEST_URL,
-        )
-    ]
-    /// Url for testing proxy endpointes
-    pub test_url: Url,
+        short,
+        long,
+        global = true,
+        default_value = "table",
+        possible_values = &["table", "json", "yaml"],
+    )]
+    /// Output format of commands
+    pub output: OutputFormat,
 }
 
 impl Default for Flags {
     fn default() -> Self {
         Self {
             verbose: 0,
-            timeout: 2000,
+            timeout: None,
             config_dir: None,
             config_path: None,
-            test_url: Url::parse(DEFAULT_TEST_URL).unwrap(),
+            test_url: None,
+            server: None,
+            websocket: false,
+            output: OutputFormat::Table,
         }
     }
 }
@@ -73,12 +93,56 @@ impl Flags {
         }
     }
 
+    /// Server given by `--server`, or the active one if it's omitted
+    pub fn get_server(&self, config: &Config) -> InteractiveResult<Option<Server>> {
+        match self.server {
+            Some(ref server) => config
+                .find_server(server)
+                .cloned()
+                .map(Some)
+                .ok_or(InteractiveError::ServerNotFound),
+            None => Ok(config.using_server().cloned()),
+        }
+    }
+
+    /// Fill `timeout` and `test_url` with those of the server, if they are
+    /// not given
+    pub fn apply_server_defaults(&mut self) -> InteractiveResult<()> {
+        let config = self.get_config()?;
+        if let Some(server) = self.get_server(&config)? {
+            self.timeout = self.timeout.or(server.timeout);
+            self.test_url = self.test_url.take().or(server.test_url);
+        }
+        Ok(())
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout.unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    pub fn test_url(&self) -> Url {
+        self.test_url
+            .clone()
+            .unwrap_or_else(|| Url::parse(DEFAULT_TEST_URL).unwrap())
+    }
+
     pub fn connect_server_from_config(&self) -> InteractiveResult<Clash> {
         let config = self.get_config()?;
-        let server = config
-            .using_server()
-            .ok_or(InteractiveError::ServerNotFound)?
-            .to_owned();
-        server.into_clash_with_timeout(Some(Duration::from_millis(self.timeout)))
+        let server = self
+            .get_server(&config)?
+            .ok_or(InteractiveError::ServerNotFound)?;
+        Ok(server
+            .into_clash_builder()?
+            .timeout(Some(Duration::from_millis(self.timeout())))
+            .transport(self.transport())
+            .build())
+    }
+
+    pub fn transport(&self) -> LongHaulTransport {
+        if self.websocket {
+            LongHaulTransport::WebSocket
+        } else {
+            LongHaulTransport::Chunked
+        }
     }
 }
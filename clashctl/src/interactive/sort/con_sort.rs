@@ -1,12 +1,18 @@
-use clashctl_core::model::ConnectionWithSpeed;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+};
+
+use clashctl_core::model::{ConnectionWithSpeed, Metadata};
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
 
-use crate::{EndlessSelf, OrderBy, SortMethod, SortOrder};
+use crate::{cycle_variant, EndlessSelf, OrderBy, SortMethod, SortOrder};
 
 #[derive(
     Debug,
     Clone,
+    Copy,
     PartialEq,
     Eq,
     PartialOrd,
@@ -15,9 +21,13 @@ use crate::{EndlessSelf, OrderBy, SortMethod, SortOrder};
     Deserialize,
     SmartDefault,
     strum::EnumIter,
+    strum::EnumString,
+    strum::Display,
+    strum::EnumVariantNames,
 )]
 #[serde(rename_all = "lowercase")]
-enum ConSortBy {
+#[strum(ascii_case_insensitive)]
+pub enum ConSortBy {
     Host,
     Down,
     Up,
@@ -32,19 +42,54 @@ enum ConSortBy {
     Type,
 }
 
+fn port(port: &str) -> u16 {
+    port.parse().unwrap_or_default()
+}
+
+fn src(meta: &Metadata) -> (&str, u16) {
+    (&meta.source_ip, port(&meta.source_port))
+}
+
+fn dest(meta: &Metadata) -> (&str, u16) {
+    (&meta.destination_ip, port(&meta.destination_port))
+}
+
 impl SortMethod<ConnectionWithSpeed> for ConSortBy {
-    fn sort_fn(&self, _a: &ConnectionWithSpeed, _b: &ConnectionWithSpeed) -> std::cmp::Ordering {
-        todo!()
+    fn sort_fn(&self, a: &ConnectionWithSpeed, b: &ConnectionWithSpeed) -> Ordering {
+        let (con_a, con_b) = (&a.connection, &b.connection);
+        let (meta_a, meta_b) = (&con_a.metadata, &con_b.metadata);
+        match self {
+            ConSortBy::Host => meta_a
+                .host
+                .cmp(&meta_b.host)
+                .then_with(|| dest(meta_a).cmp(&dest(meta_b))),
+            ConSortBy::Down => con_a.download.cmp(&con_b.download),
+            ConSortBy::Up => con_a.upload.cmp(&con_b.upload),
+            ConSortBy::DownSpeed => a.download.cmp(&b.download),
+            ConSortBy::UpSpeed => a.upload.cmp(&b.upload),
+            ConSortBy::Chains => con_a.chains.cmp(&con_b.chains),
+            ConSortBy::Rule => con_a
+                .rule
+                .cmp(&con_b.rule)
+                .then_with(|| con_a.rule_payload.cmp(&con_b.rule_payload)),
+            ConSortBy::Time => con_a.start.cmp(&con_b.start),
+            ConSortBy::Src => src(meta_a).cmp(&src(meta_b)),
+            ConSortBy::Dest => dest(meta_a).cmp(&dest(meta_b)),
+            ConSortBy::Type => meta_a
+                .connection_type
+                .cmp(&meta_b.connection_type)
+                .then_with(|| meta_a.network.cmp(&meta_b.network)),
+        }
     }
 }
 
 impl EndlessSelf for ConSortBy {
     fn next_self(&mut self) {
-        todo!()
+        cycle_variant(self, true)
     }
 
     fn prev_self(&mut self) {
-        todo!()
+        cycle_variant(self, false)
     }
 }
 
@@ -55,18 +100,84 @@ pub struct ConSort {
     order: SortOrder,
 }
 
+impl ConSort {
+    #[inline]
+    pub fn new(by: ConSortBy, order: SortOrder) -> Self {
+        Self { by, order }
+    }
+
+    #[inline]
+    pub fn by(&self) -> ConSortBy {
+        self.by
+    }
+
+    #[inline]
+    pub fn order(&self) -> SortOrder {
+        self.order
+    }
+}
+
 impl EndlessSelf for ConSort {
     fn next_self(&mut self) {
-        todo!()
+        match self.order {
+            SortOrder::Ascendant => self.order = SortOrder::Descendant,
+            SortOrder::Descendant => {
+                self.by.next_self();
+                self.order = SortOrder::Ascendant
+            }
+        }
     }
 
     fn prev_self(&mut self) {
-        todo!()
+        match self.order {
+            SortOrder::Descendant => self.order = SortOrder::Ascendant,
+            SortOrder::Ascendant => {
+                self.by.prev_self();
+                self.order = SortOrder::Descendant
+            }
+        }
+    }
+}
+
+impl Display for ConSort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.by,
+            match self.order {
+                SortOrder::Ascendant => "▲",
+                SortOrder::Descendant => "▼",
+            }
+        )
     }
 }
 
 impl SortMethod<ConnectionWithSpeed> for ConSort {
-    fn sort_fn(&self, a: &ConnectionWithSpeed, b: &ConnectionWithSpeed) -> std::cmp::Ordering {
+    fn sort_fn(&self, a: &ConnectionWithSpeed, b: &ConnectionWithSpeed) -> Ordering {
         self.by.sort_fn(a, b).order_by(self.order)
     }
 }
+
+#[test]
+fn test_con_sort_cycle() {
+    use strum::IntoEnumIterator;
+
+    let start = ConSort::new(ConSortBy::Host, SortOrder::Ascendant);
+    let mut sort = start.clone();
+    let count = ConSortBy::iter().count() * 2;
+
+    for _ in 0..count {
+        sort.next_self();
+    }
+    assert_eq!(sort, start);
+
+    sort.next_self();
+    assert_eq!(sort, ConSort::new(ConSortBy::Host, SortOrder::Descendant));
+    sort.next_self();
+    assert_eq!(sort, ConSort::new(ConSortBy::Down, SortOrder::Ascendant));
+    sort.prev_self();
+    sort.prev_self();
+    sort.prev_self();
+    assert_eq!(sort, ConSort::new(ConSortBy::Type, SortOrder::Descendant));
+}
@@ -1,7 +1,11 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+};
 
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
+use strum::IntoEnumIterator;
 
 mod_use::mod_use![con_sort, proxy_sort, rule_sort];
 
@@ -19,6 +23,18 @@ pub trait EndlessSelf {
     fn prev_self(&mut self);
 }
 
+/// Step `value` to the next (or previous, if not `forward`) variant of its
+/// enum, wrapping around at both ends. Shared by plain `*SortBy` enums
+pub fn cycle_variant<T>(value: &mut T, forward: bool)
+where
+    T: IntoEnumIterator + PartialEq + Copy,
+{
+    let all = T::iter().collect::<Vec<_>>();
+    let index = all.iter().position(|x| x == value).unwrap_or_default();
+    let step = if forward { 1 } else { all.len() - 1 };
+    *value = all[(index + step) % all.len()]
+}
+
 #[derive(
     Debug,
     Clone,
@@ -65,9 +81,9 @@ impl Noop {
     }
 }
 
-impl ToString for Noop {
-    fn to_string(&self) -> String {
-        "".into()
+impl Display for Noop {
+    fn fmt(&self, _: &mut Formatter<'_>) -> fmt::Result {
+        Ok(())
     }
 }
 
@@ -1,4 +1,7 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+};
 
 use clashctl_core::{model::Proxy, strum};
 use serde::{Deserialize, Serialize};
@@ -164,9 +167,10 @@ impl EndlessSelf for ProxySort {
     }
 }
 
-impl ToString for ProxySort {
-    fn to_string(&self) -> String {
-        format!(
+impl Display for ProxySort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
             "{} {}",
             self.by,
             match self.order {
@@ -188,7 +192,7 @@ impl SortMethod<(&String, &Proxy)> for ProxySort {
         let ret = match self.by() {
             ProxySortBy::Type => a.1.proxy_type.cmp(&b.1.proxy_type),
             ProxySortBy::Name => a.0.cmp(b.0),
-            ProxySortBy::Delay => match (a.1.history.get(0), b.1.history.get(0)) {
+            ProxySortBy::Delay => match (a.1.history.first(), b.1.history.first()) {
                 // 0 delay means unable to connect, so handle exceptionally
                 // This will push all 0-delay proxies to the end of list
                 (Some(l_history), Some(r_history)) => match (l_history.delay, r_history.delay) {
@@ -1,9 +1,14 @@
-use crate::{EndlessSelf, OrderBy, SortMethod, SortOrder};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+};
 
-use clashctl_core::model::Rule;
+use clashctl_core::model::{Rule, RuleWithStats};
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
 
+use crate::{cycle_variant, EndlessSelf, OrderBy, SortMethod, SortOrder};
+
 #[derive(
     Debug,
     Clone,
@@ -96,6 +101,7 @@ impl EndlessSelf for RuleSort {
             (Proxy, Descendant) => Self::by_payload_asc(),
         }
     }
+
     fn prev_self(&mut self) {
         use RuleSortBy::*;
         use SortOrder::*;
@@ -111,9 +117,10 @@ impl EndlessSelf for RuleSort {
     }
 }
 
-impl ToString for RuleSort {
-    fn to_string(&self) -> String {
-        format!(
+impl Display for RuleSort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
             "{} {}",
             self.by,
             match self.order {
@@ -125,7 +132,7 @@ impl ToString for RuleSort {
 }
 
 impl SortMethod<Rule> for RuleSort {
-    fn sort_fn(&self, a: &Rule, b: &Rule) -> std::cmp::Ordering {
+    fn sort_fn(&self, a: &Rule, b: &Rule) -> Ordering {
         match self.by {
             RuleSortBy::Payload => a.payload.cmp(&b.payload),
             RuleSortBy::Proxy => a.proxy.cmp(&b.proxy),
@@ -134,3 +141,142 @@ impl SortMethod<Rule> for RuleSort {
         .order_by(self.order)
     }
 }
+
+/// Like [`RuleSortBy`], plus hit statistics only known on Rules page
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    SmartDefault,
+    strum::EnumIter,
+    strum::EnumString,
+    strum::Display,
+    strum::EnumVariantNames,
+)]
+#[strum(ascii_case_insensitive)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleStatsSortBy {
+    #[default]
+    Payload,
+    Proxy,
+    Type,
+    /// Number of connections matched
+    Hits,
+    /// Traffic of connections matched
+    Traffic,
+    /// Start time of latest connection matched
+    LastHit,
+}
+
+impl SortMethod<RuleWithStats> for RuleStatsSortBy {
+    fn sort_fn(&self, a: &RuleWithStats, b: &RuleWithStats) -> Ordering {
+        match self {
+            RuleStatsSortBy::Payload => a.rule.payload.cmp(&b.rule.payload),
+            RuleStatsSortBy::Proxy => a.rule.proxy.cmp(&b.rule.proxy),
+            RuleStatsSortBy::Type => a.rule.rule_type.cmp(&b.rule.rule_type),
+            RuleStatsSortBy::Hits => a.stats.hits.cmp(&b.stats.hits),
+            RuleStatsSortBy::Traffic => a.stats.traffic.cmp(&b.stats.traffic),
+            RuleStatsSortBy::LastHit => a.stats.last_hit.cmp(&b.stats.last_hit),
+        }
+    }
+}
+
+impl EndlessSelf for RuleStatsSortBy {
+    fn next_self(&mut self) {
+        cycle_variant(self, true)
+    }
+
+    fn prev_self(&mut self) {
+        cycle_variant(self, false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub struct RuleStatsSort {
+    by: RuleStatsSortBy,
+    order: SortOrder,
+}
+
+impl RuleStatsSort {
+    #[inline]
+    pub fn new(by: RuleStatsSortBy, order: SortOrder) -> Self {
+        Self { by, order }
+    }
+
+    #[inline]
+    pub fn by(&self) -> RuleStatsSortBy {
+        self.by
+    }
+
+    #[inline]
+    pub fn order(&self) -> SortOrder {
+        self.order
+    }
+}
+
+impl EndlessSelf for RuleStatsSort {
+    fn next_self(&mut self) {
+        match self.order {
+            SortOrder::Ascendant => self.order = SortOrder::Descendant,
+            SortOrder::Descendant => {
+                self.by.next_self();
+                self.order = SortOrder::Ascendant
+            }
+        }
+    }
+
+    fn prev_self(&mut self) {
+        match self.order {
+            SortOrder::Descendant => self.order = SortOrder::Ascendant,
+            SortOrder::Ascendant => {
+                self.by.prev_self();
+                self.order = SortOrder::Descendant
+            }
+        }
+    }
+}
+
+impl Display for RuleStatsSort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.by,
+            match self.order {
+                SortOrder::Ascendant => "▲",
+                SortOrder::Descendant => "▼",
+            }
+        )
+    }
+}
+
+impl SortMethod<RuleWithStats> for RuleStatsSort {
+    fn sort_fn(&self, a: &RuleWithStats, b: &RuleWithStats) -> Ordering {
+        self.by.sort_fn(a, b).order_by(self.order)
+    }
+}
+
+#[test]
+fn test_rule_stats_sort_cycle() {
+    use strum::IntoEnumIterator;
+
+    let start = RuleStatsSort::new(RuleStatsSortBy::Payload, SortOrder::Ascendant);
+    let mut sort = start.clone();
+    for _ in 0..RuleStatsSortBy::iter().count() * 2 {
+        sort.next_self();
+    }
+    assert_eq!(sort, start);
+
+    sort.prev_self();
+    assert_eq!(
+        sort,
+        RuleStatsSort::new(RuleStatsSortBy::LastHit, SortOrder::Descendant)
+    );
+}
@@ -1,27 +1,50 @@
-mod_use::mod_use![command, proxy_render, utils, error, interactive, ui];
+mod_use::mod_use![command, proxy_render, output, utils, error, interactive, ui];
+
+use std::process::ExitCode;
 
 pub use clap;
 use log::debug;
-use ui::main_loop;
+use owo_colors::OwoColorize;
 
-use crate::{clap::Parser, Cmd, Opts};
+use crate::clap::Parser;
 
-pub fn run() {
-    let opts = Opts::parse();
+pub fn run() -> ExitCode {
+    let mut opts = Opts::parse();
     opts.init_logger();
     debug!("Opts: {:#?}", opts);
 
-    if let Err(e) = match opts.cmd {
+    // Servers are managed by name, and completion needs no config
+    if !matches!(opts.cmd, Some(Cmd::Server(_) | Cmd::Completion(_))) {
+        if let Err(e) = opts.flag.apply_server_defaults() {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let result = match opts.cmd {
         None => main_loop(Default::default(), opts.flag).map_err(Into::into),
         Some(Cmd::Tui(opt)) => main_loop(opt, opts.flag).map_err(Into::into),
         Some(Cmd::Proxy(sub)) => sub.handle(&opts.flag),
+        Some(Cmd::Provider(sub)) => sub.handle(&opts.flag),
         Some(Cmd::Server(sub)) => sub.handle(&opts.flag),
+        Some(Cmd::Conn(sub)) => sub.handle(&opts.flag),
+        Some(Cmd::Mode(arg)) => arg.handle(&opts.flag),
+        Some(Cmd::Config(sub)) => sub.handle(&opts.flag),
+        Some(Cmd::Rule(sub)) => sub.handle(&opts.flag),
         Some(Cmd::Completion(arg)) => arg.handle(),
-    } {
-        eprintln!("{}", e)
+    };
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            if let Some(hint) = e.hint() {
+                eprintln!("{} {}", "Hint:".yellow(), hint)
+            }
+            ExitCode::FAILURE
+        }
     }
 }
 
-fn main() {
+fn main() -> ExitCode {
     run()
 }
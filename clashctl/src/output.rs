@@ -0,0 +1,44 @@
+use serde::Serialize;
+
+use crate::Result;
+
+/// Format of what commands print, set with the global `--output` flag
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    strum::EnumString,
+    strum::Display,
+    strum::EnumVariantNames,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human readable, colored table
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    pub fn is_table(&self) -> bool {
+        matches!(self, Self::Table)
+    }
+
+    /// Print `value` as JSON or YAML, or call `table` to print it as a table
+    pub fn print_or<T, F>(&self, value: &T, table: F) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+        F: FnOnce(),
+    {
+        match self {
+            Self::Table => table(),
+            Self::Json => println!("{}", serde_json::to_string(value)?),
+            Self::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        }
+        Ok(())
+    }
+}
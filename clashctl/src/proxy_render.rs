@@ -9,6 +9,8 @@ use crate::{
 };
 
 pub trait RenderList {
+    /// Proxies filtered and sorted as in plain mode
+    fn plain_list(&self, opt: &ProxyListOpt) -> Vec<(&String, &Proxy)>;
     fn render_list(&self, opt: &ProxyListOpt);
     fn render_plain(&self, opt: &ProxyListOpt);
     fn render_tree(&self, opt: &ProxyListOpt);
@@ -34,13 +36,13 @@ impl RenderList for Proxies {
         println!("{:-<1$}", "", terminal_width as usize);
     }
 
-    fn render_plain(&self, opt: &ProxyListOpt) {
+    fn plain_list(&self, opt: &ProxyListOpt) -> Vec<(&String, &Proxy)> {
         let mut list = self.iter().collect::<Vec<_>>();
         let sort_method = ProxySort::new(opt.sort_by, opt.sort_order);
 
         list.sort_with(&sort_method);
 
-        let iter = if opt.reverse {
+        if opt.reverse {
             Either::Left(list.into_iter().rev())
         } else {
             Either::Right(list.into_iter())
@@ -54,12 +56,15 @@ impl RenderList for Proxies {
                 // When types included is specified
                 opt.include.contains(proxy_type)
             }
-        });
+        })
+        .collect()
+    }
 
-        for (name, proxy) in iter {
+    fn render_plain(&self, opt: &ProxyListOpt) {
+        for (name, proxy) in self.plain_list(opt) {
             let delay = proxy
                 .history
-                .get(0)
+                .first()
                 .map(|x| match x.delay {
                     0 => "?".to_owned(),
                     delay => delay.to_string(),
@@ -109,7 +114,7 @@ impl RenderList for Proxies {
             ) in members
             {
                 let delay = history
-                    .get(0)
+                    .first()
                     .map(|x| match x.delay {
                         0 => "?".to_owned(),
                         delay => delay.to_string(),
@@ -1,5 +1,12 @@
+use clashctl_core::model::ConfigPatch;
+
 #[derive(Debug, Clone)]
 pub enum Action {
     TestLatency { proxies: Vec<String> },
     ApplySelection { group: String, proxy: String },
+    CloseConnections { ids: Vec<String> },
+    CloseAllConnections,
+    PatchConfig(ConfigPatch),
+    UpdateProvider { name: String },
+    HealthcheckProvider { name: String },
 }
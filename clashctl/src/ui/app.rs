@@ -24,8 +24,10 @@ use crate::{
     interactive::Flags,
     servo,
     ui::{
-        components::Tabs, get_config, init_config, pages::route, Interval, LoggerBuilder,
-        TicksCounter, TuiOpt, TuiResult, TuiStates,
+        components::{ConfirmPopup, Tabs},
+        get_config, init_config,
+        pages::route,
+        Interval, LoggerBuilder, TicksCounter, TuiOpt, TuiResult, TuiStates,
     },
 };
 
@@ -56,7 +58,7 @@ fn wrap_up(mut terminal: Terminal<Backend>) -> TuiResult<()> {
 
 pub fn main_loop(opt: TuiOpt, flag: Flags) -> TuiResult<()> {
     let config = flag.get_config()?;
-    if config.using_server().is_none() {
+    if flag.get_server(&config)?.is_none() {
         println!(
             "{} No API server configured yet. Use this command to add a server:\n\n  $ {}",
             "WARN:".red(),
@@ -109,6 +111,8 @@ pub fn main_loop(opt: TuiOpt, flag: Flags) -> TuiResult<()> {
                     should_quit = true;
                 }
             }
+            // Key presses are only mapped into `Event::Quit` by the state
+            should_quit |= state.should_quit;
             if should_quit {
                 break;
             }
@@ -178,4 +182,8 @@ fn render(state: &TuiStates, f: &mut Frame<Backend>) {
     let main = layout[1];
 
     route(state, main, f);
+
+    if state.pending_action.is_some() {
+        f.render_widget(ConfirmPopup::new(state), main);
+    }
 }
@@ -0,0 +1,45 @@
+use tui::{
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::ui::{
+    define_widget,
+    utils::{centered_rect, get_focused_block},
+};
+
+define_widget!(ConfirmPopup);
+
+impl<'a> Widget for ConfirmPopup<'a> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let pending = match self.state.pending_action {
+            Some(ref pending) => pending,
+            None => return,
+        };
+        let width = (pending.prompt.chars().count() as u16 + 6).max(30);
+        let area = centered_rect(width, 6, area);
+        let key = Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD);
+
+        let text = vec![
+            Spans::from(pending.prompt.as_str()),
+            Spans::default(),
+            Spans(vec![
+                Span::styled("[y]", key),
+                Span::raw(" Confirm    "),
+                Span::styled("[n]", key),
+                Span::raw(" Cancel"),
+            ]),
+        ];
+
+        Clear.render(area, buf);
+        Paragraph::new(text)
+            .block(get_focused_block("Confirm"))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+}
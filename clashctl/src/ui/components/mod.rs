@@ -1,5 +1,6 @@
 mod_use::mod_use![
     block_footer,
+    confirm,
     constants,
     movable_list,
     proxy,
@@ -0,0 +1,170 @@
+use tui::{
+    style::{Color, Modifier, Style},
+    text::Spans,
+};
+
+use crate::{
+    tagged_footer,
+    ui::{components::FooterItem, utils::IntoSpans},
+    FilterEvent,
+};
+
+/// Case-insensitive text filter of list items, started with `/`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListFilter {
+    query: String,
+    editing: bool,
+}
+
+impl ListFilter {
+    const HIGHLIGHT_STYLE: Style = Style {
+        fg: Some(Color::Black),
+        bg: Some(Color::Yellow),
+        add_modifier: Modifier::BOLD,
+        sub_modifier: Modifier::empty(),
+    };
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Whether typed keys should go into the query
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    /// Whether items are being filtered
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    /// Apply the event, returns whether the query changed.
+    /// [`FilterEvent::Next`] and [`FilterEvent::Prev`] are left to the
+    /// list.
+    pub fn handle(&mut self, event: &FilterEvent) -> bool {
+        match event {
+            FilterEvent::Start => {
+                self.editing = true;
+                !std::mem::take(&mut self.query).is_empty()
+            }
+            FilterEvent::Push(char) => {
+                self.query.push(*char);
+                true
+            }
+            FilterEvent::Pop => self.query.pop().is_some(),
+            FilterEvent::Confirm => {
+                self.editing = false;
+                false
+            }
+            FilterEvent::Clear => {
+                self.editing = false;
+                !std::mem::take(&mut self.query).is_empty()
+            }
+            FilterEvent::Next | FilterEvent::Prev => false,
+        }
+    }
+
+    /// Char index of the first match in `content`
+    pub fn find(&self, content: &str) -> Option<usize> {
+        let content = content.chars().collect::<Vec<_>>();
+        self.find_in(&content)
+    }
+
+    pub fn matches(&self, content: &str) -> bool {
+        !self.is_active() || self.find(content).is_some()
+    }
+
+    pub fn matches_spans(&self, spans: &Spans) -> bool {
+        !self.is_active() || self.find(&spans_text(spans)).is_some()
+    }
+
+    /// Highlight all matches of the query in `spans`
+    pub fn highlight<'a>(&self, spans: Spans<'a>) -> Spans<'a> {
+        if !self.is_active() {
+            return spans;
+        }
+        let mut chars = spans
+            .0
+            .iter()
+            .flat_map(|x| x.content.chars().map(|c| (x.style, c)))
+            .collect::<Vec<_>>();
+        let content = chars.iter().map(|(_, c)| *c).collect::<Vec<_>>();
+        let len = self.query.chars().count();
+
+        let mut start = 0;
+        while let Some(pos) = self.find_in(&content[start..]) {
+            let pos = start + pos;
+            chars[pos..pos + len]
+                .iter_mut()
+                .for_each(|(style, _)| *style = style.patch(Self::HIGHLIGHT_STYLE));
+            start = pos + len;
+        }
+        chars.into_spans()
+    }
+
+    pub fn footer<'a>(&self, style: Style) -> Option<FooterItem<'a>> {
+        if !self.editing && !self.is_active() {
+            return None;
+        }
+        let content = if self.editing {
+            format!("/{}▏", self.query)
+        } else {
+            format!("/{}", self.query)
+        };
+        Some(tagged_footer("Filter", style, content).into())
+    }
+
+    fn find_in(&self, content: &[char]) -> Option<usize> {
+        let query = self.query.chars().collect::<Vec<_>>();
+        if query.is_empty() || query.len() > content.len() {
+            return None;
+        }
+        content.windows(query.len()).position(|window| {
+            window
+                .iter()
+                .zip(&query)
+                .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+        })
+    }
+}
+
+pub fn spans_text(spans: &Spans) -> String {
+    spans.0.iter().map(|x| x.content.as_ref()).collect()
+}
+
+#[test]
+fn test_list_filter() {
+    use tui::text::Span;
+
+    let mut filter = ListFilter::default();
+    assert!(filter.matches("anything"));
+
+    for event in [
+        FilterEvent::Start,
+        FilterEvent::Push('g'),
+        FilterEvent::Push('O'),
+    ] {
+        filter.handle(&event);
+    }
+    assert!(filter.is_editing());
+    assert_eq!(filter.find("www.Google.com"), Some(4));
+    assert_eq!(filter.find("代理 go"), Some(3));
+    assert!(!filter.matches("example.com"));
+
+    let style = Style::default().fg(Color::Red);
+    let spans = filter.highlight(Spans(vec![Span::styled("Go", style), Span::raw("ogle-go")]));
+    let highlight = style.patch(ListFilter::HIGHLIGHT_STYLE);
+    assert_eq!(
+        spans,
+        Spans(vec![
+            Span::styled("Go", highlight),
+            Span::raw("ogle-"),
+            Span::styled("go", Style::default().patch(ListFilter::HIGHLIGHT_STYLE)),
+        ])
+    );
+
+    filter.handle(&FilterEvent::Confirm);
+    assert!(!filter.is_editing() && filter.is_active());
+    assert!(filter.handle(&FilterEvent::Clear));
+    assert!(!filter.is_active());
+}
@@ -1 +1 @@
-mod_use::mod_use![item, state, widget];
+mod_use::mod_use![filter, item, state, widget];
@@ -12,10 +12,11 @@ use smart_default::SmartDefault;
 use crate::{
     interactive::{EndlessSelf, SortMethod, Sortable},
     ui::{
-        components::{MovableListItem, ProxyTree},
+        components::{spans_text, ListFilter, MovableListItem, ProxyTree},
         utils::Coord,
     },
-    Action, ConListState, DebugListState, ListEvent, LogListState, RuleListState,
+    Action, ConListState, DebugListState, FilterEvent, ListEvent, LogListState, ProviderListState,
+    RuleListState,
 };
 
 macro_rules! impl_setter {
@@ -54,6 +55,8 @@ where
 
     impl_setter!(dsc_index, reverse_index, true);
 
+    impl_setter!(selectable, true);
+
     impl_setter!(items, Vec<T>);
 
     impl_setter!(padding, u16);
@@ -81,6 +84,10 @@ where
         }
     }
 
+    pub fn current_sort(&self) -> &S {
+        &self.sort
+    }
+
     pub fn placeholder<P: Into<Cow<'a, str>>>(&mut self, content: P) -> &mut Self {
         self.placeholder = Some(content.into());
         self
@@ -92,10 +99,62 @@ where
     }
 
     pub fn push(&mut self, item: T) {
-        self.items.push(item);
-        if self.offset.hold {
+        if self.offset.hold && self.matches(&item) {
             self.offset.y += 1;
         }
+        self.items.push(item);
+    }
+
+    /// Whether `item` matches current filter. Spans are only built when a
+    /// filter is set, as lists like connections are long
+    fn matches(&self, item: &T) -> bool {
+        !self.filter.is_active() || self.filter.matches_spans(&item.to_spans())
+    }
+
+    /// Items that match current filter
+    pub fn visible(&self) -> impl DoubleEndedIterator<Item = &T> + use<'_, 'a, T, S> {
+        self.items.iter().filter(|x| self.matches(x))
+    }
+
+    /// Item on top of the list in hold mode, if the list is selectable
+    pub fn selected(&self) -> Option<&T> {
+        if !self.selectable || !self.offset.hold {
+            return None;
+        }
+        let y = self.offset.y.min(self.visible_len().saturating_sub(1));
+        self.visible().rev().nth(y)
+    }
+
+    pub fn visible_len(&self) -> usize {
+        if self.filter.is_active() {
+            self.visible().count()
+        } else {
+            self.items.len()
+        }
+    }
+
+    /// Move to next or previous matching item, and scroll horizontally so the
+    /// match is visible
+    fn jump(&mut self, forward: bool) {
+        let len = self.visible_len();
+        if len == 0 || !self.filter.is_active() {
+            return;
+        }
+        let y = self.offset.y.min(len - 1);
+        let y = if forward {
+            (y + 1) % len
+        } else {
+            (y + len - 1) % len
+        };
+        let x = self
+            .visible()
+            .rev()
+            .nth(y)
+            .and_then(|item| self.filter.find(&spans_text(&item.to_spans())))
+            // Leave some context before the match
+            .map(|pos| pos.saturating_sub(8))
+            .unwrap_or_default();
+        self.offset = Coord { x, y, hold: true };
     }
 }
 
@@ -110,6 +169,8 @@ pub struct MovableListState<'a, T: MovableListItem<'a>, S: Default> {
     pub(super) sort: S,
     pub(super) with_index: bool,
     pub(super) reverse_index: bool,
+    pub(super) selectable: bool,
+    pub(super) filter: ListFilter,
 }
 
 impl<'a, T, S> Deref for MovableListState<'a, T, S>
@@ -176,6 +237,10 @@ pub trait MovableListManage {
 
     fn handle(&mut self, event: ListEvent) -> Option<Action>;
     fn offset(&self) -> &Coord;
+
+    fn list_filter(&self) -> &ListFilter;
+
+    fn handle_filter(&mut self, event: FilterEvent) -> &mut Self;
 }
 
 impl<'a, T, S> MovableListManage for MovableListState<'a, T, S>
@@ -205,7 +270,7 @@ where
 
     fn current_pos(&self) -> Coord {
         let x = self.offset.x;
-        let y = self.len().saturating_sub(self.offset.y);
+        let y = self.visible_len().saturating_sub(self.offset.y);
         Coord {
             x,
             y,
@@ -237,7 +302,7 @@ where
     }
 
     fn handle(&mut self, event: ListEvent) -> Option<Action> {
-        let len = self.len().saturating_sub(1);
+        let len = self.visible_len().saturating_sub(1);
         let offset = &mut self.offset;
 
         if !offset.hold {
@@ -261,12 +326,30 @@ where
     fn offset(&self) -> &Coord {
         &self.offset
     }
+
+    fn list_filter(&self) -> &ListFilter {
+        &self.filter
+    }
+
+    fn handle_filter(&mut self, event: FilterEvent) -> &mut Self {
+        if self.filter.handle(&event) {
+            self.offset.x = 0;
+            self.offset.y = 0;
+        }
+        match event {
+            FilterEvent::Next => self.jump(true),
+            FilterEvent::Prev => self.jump(false),
+            _ => {}
+        }
+        self
+    }
 }
 
 pub enum MovableListManager<'a, 'own> {
     Log(&'own mut LogListState<'a>),
     Connection(&'own mut ConListState<'a>),
     Rule(&'own mut RuleListState<'a>),
+    Provider(&'own mut ProviderListState<'a>),
     Event(&'own mut DebugListState<'a>),
     Proxy(&'own mut ProxyTree<'a>),
 }
@@ -279,6 +362,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.sort();
             }
@@ -293,6 +377,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.next_sort();
             }
@@ -307,6 +392,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.prev_sort();
             }
@@ -321,6 +407,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.current_pos()
             }
@@ -334,6 +421,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.len()
             }
@@ -347,6 +435,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.is_empty()
             }
@@ -360,6 +449,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.toggle();
             }
@@ -374,6 +464,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.end();
             }
@@ -388,6 +479,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.hold();
             }
@@ -402,6 +494,7 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.handle(event)
             }
@@ -415,9 +508,39 @@ impl<'a, 'own> MovableListManage for MovableListManager<'a, 'own> {
             Self::Event(inner) |
             Self::Rule(inner) |
             Self::Connection(inner) |
+            Self::Provider(inner) |
             Self::Proxy(inner) => {
                 inner.offset()
             }
         )
     }
+
+    fn list_filter(&self) -> &ListFilter {
+        match_any!(
+            self,
+            Self::Log(inner) |
+            Self::Event(inner) |
+            Self::Rule(inner) |
+            Self::Connection(inner) |
+            Self::Provider(inner) |
+            Self::Proxy(inner) => {
+                inner.list_filter()
+            }
+        )
+    }
+
+    fn handle_filter(&mut self, event: FilterEvent) -> &mut Self {
+        match_any!(
+            self,
+            Self::Log(inner) |
+            Self::Event(inner) |
+            Self::Rule(inner) |
+            Self::Connection(inner) |
+            Self::Provider(inner) |
+            Self::Proxy(inner) => {
+                inner.handle_filter(event);
+            }
+        );
+        self
+    }
 }
@@ -65,6 +65,9 @@ where
             if !sort_str.is_empty() {
                 footer.push_left(tagged_footer("Sort", style, sort_str).into());
             }
+            if let Some(filter) = self.state.filter.footer(style) {
+                footer.push_left(filter);
+            }
         } else {
             let style = Style::default()
                 .fg(Color::Blue)
@@ -78,6 +81,9 @@ where
             if !sort_str.is_empty() {
                 footer.push_left(tagged_footer("Sort", style, sort_str).into());
             }
+            if let Some(filter) = self.state.filter.footer(style) {
+                footer.push_left(filter);
+            }
         }
 
         let widget = FooterWidget::new(&footer);
@@ -92,7 +98,7 @@ where
     MovableListState<'a, T, S>: MovableListManage,
 {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
-        let num = self.state.items.len();
+        let num = self.state.visible_len();
 
         let offset = self.state.offset;
 
@@ -134,18 +140,19 @@ where
                 .saturating_sub(index_width));
         let with_index = self.state.with_index;
         let rev_index = self.state.reverse_index;
+        let selectable = self.state.selectable && offset.hold;
 
         // Get that portion of items
         let items = if num != 0 {
+            let filter = &self.state.filter;
             self.state
-                .items
-                .iter()
+                .visible()
                 .rev()
                 .skip(y_offset)
-                .take(height as usize)
+                .take(height)
                 .enumerate()
                 .map(|(i, x)| {
-                    let content = x.to_spans();
+                    let content = filter.highlight(x.to_spans());
                     let x_width = content.width();
                     let content = spans_window_owned(content, &x_range);
 
@@ -155,6 +162,12 @@ where
                         content
                     };
 
+                    if selectable && i == 0 {
+                        spans.0.iter_mut().for_each(|span| {
+                            span.style = span.style.add_modifier(Modifier::REVERSED)
+                        });
+                    }
+
                     if with_index {
                         let cur_index = if rev_index {
                             num - i - y_offset
@@ -172,6 +185,11 @@ where
                     ListItem::new(spans)
                 })
                 .collect::<Vec<_>>()
+        } else if self.state.filter.is_active() {
+            vec![ListItem::new(Span::raw(format!(
+                "Nothing matches \"{}\"",
+                self.state.filter.query()
+            )))]
         } else {
             vec![ListItem::new(Span::raw(
                 self.state
@@ -36,7 +36,7 @@ impl<'a> ProxyGroup<'a> {
         &self.members
     }
 
-    pub fn get_summary_widget(&self) -> impl Iterator<Item = Span> {
+    pub fn get_summary_widget(&self) -> impl Iterator<Item = Span<'_>> {
         self.members.iter().map(|x| {
             if x.proxy_type.is_normal() {
                 match x.history {
@@ -172,7 +172,7 @@ impl<'a> ProxyGroup<'a> {
         ret
     }
 
-    fn get_delay_style(delay: u64) -> Style {
+    pub fn get_delay_style(delay: u64) -> Style {
         match delay {
             0 => Consts::NO_LATENCY_STYLE,
             1..=200 => Consts::LOW_LATENCY_STYLE,
@@ -181,7 +181,7 @@ impl<'a> ProxyGroup<'a> {
         }
     }
 
-    fn get_delay_span(delay: u64) -> Span<'static> {
+    pub fn get_delay_span(delay: u64) -> Span<'static> {
         match delay {
             0 => Consts::NO_LATENCY_SPAN,
             1..=200 => Consts::LOW_LATENCY_SPAN,
@@ -8,9 +8,9 @@ use tui::{
 };
 
 use crate::{
-    components::{Footer, FooterItem, MovableListManage, ProxyGroup, ProxyItem},
+    components::{Footer, FooterItem, ListFilter, MovableListManage, ProxyGroup, ProxyItem},
     interactive::{EndlessSelf, ProxySort, Sortable},
-    ui::{help_footer, tagged_footer, Action, Coord, ListEvent, Wrap},
+    ui::{help_footer, tagged_footer, Action, Coord, FilterEvent, ListEvent, Wrap},
 };
 
 // TODO Proxy tree furthur functions
@@ -19,7 +19,7 @@ use crate::{
 // - [X] Esc for exist expand mode
 // - [X] T for test latency of current group
 // - [X] S for switch between sorting strategies
-// - [ ] / for searching
+// - [X] / for searching
 //
 // In order for functions to be implemented, these are required:
 // - Remove Enter from InterfaceEvent::ToggleHold
@@ -32,6 +32,7 @@ pub struct ProxyTree<'a> {
     pub(super) cursor: usize,
     pub(super) testing: bool,
     pub(super) footer: Footer<'a>,
+    pub(super) filter: ListFilter,
     sort_method: ProxySort,
 }
 
@@ -43,6 +44,7 @@ impl<'a> Default for ProxyTree<'a> {
             cursor: Default::default(),
             footer: Default::default(),
             testing: Default::default(),
+            filter: Default::default(),
             sort_method: Default::default(),
         };
         ret.update_footer();
@@ -57,7 +59,7 @@ impl<'a> ProxyTree<'a> {
     }
 
     #[inline]
-    pub fn current_group(&self) -> &ProxyGroup {
+    pub fn current_group(&self) -> &ProxyGroup<'_> {
         &self.groups[self.cursor]
     }
 
@@ -78,6 +80,58 @@ impl<'a> ProxyTree<'a> {
         self.update_footer()
     }
 
+    /// Whether the group, or any of its members, matches current filter
+    pub fn is_visible(&self, group: &ProxyGroup) -> bool {
+        self.filter.matches(&group.name)
+            || group.members.iter().any(|x| self.filter.matches(&x.name))
+    }
+
+    /// Index of the next (or previous) group after `cursor` that's visible,
+    /// wrapping around if `wrap`
+    fn next_visible(&self, forward: bool, wrap: bool) -> Option<usize> {
+        let len = self.groups.len();
+        (1..len)
+            .map(|step| {
+                if forward {
+                    self.cursor + step
+                } else {
+                    self.cursor + len - step
+                }
+            })
+            // Going forward, indices past `len` have wrapped around. Going backward, those
+            // below `len` have.
+            .take_while(|index| wrap || (*index < len) == forward)
+            .map(|index| index % len)
+            .find(|index| self.is_visible(&self.groups[*index]))
+    }
+
+    /// Move to next or previous matching member when expanded, or group
+    /// otherwise
+    fn jump(&mut self, forward: bool) {
+        if !self.filter.is_active() || self.groups.is_empty() {
+            return;
+        }
+        if self.expanded {
+            let filter = &self.filter;
+            let group = &mut self.groups[self.cursor];
+            let len = group.members.len();
+            let found = (1..=len)
+                .map(|step| {
+                    if forward {
+                        (group.cursor + step) % len
+                    } else {
+                        (group.cursor + len - step) % len
+                    }
+                })
+                .find(|index| filter.matches(&group.members[*index].name));
+            if let Some(index) = found {
+                group.cursor = index
+            }
+        } else if let Some(index) = self.next_visible(forward, true) {
+            self.cursor = index
+        }
+    }
+
     pub fn sort_groups_with_frequency(&mut self, freq: &HashMap<String, usize>) -> &mut Self {
         self.groups
             .sort_by(|a, b| match (freq.get(&a.name), freq.get(&b.name)) {
@@ -117,6 +171,9 @@ impl<'a> ProxyTree<'a> {
             ];
 
             footer.append_left(&mut left);
+            if let Some(filter) = self.filter.footer(style) {
+                footer.push_left(filter);
+            }
 
             let name = FooterItem::span(Span::styled(group_name, style)).wrapped();
             footer.push_right(name);
@@ -146,6 +203,9 @@ impl<'a> ProxyTree<'a> {
             });
 
             footer.push_left(tagged_footer("Sort", style, self.sort_method).into());
+            if let Some(filter) = self.filter.footer(style) {
+                footer.push_left(filter);
+            }
 
             if let Some(ref now) = current_group.members[current_group.cursor].now {
                 footer.push_right(FooterItem::span(Span::raw(now.to_owned())).wrapped());
@@ -288,38 +348,26 @@ impl<'a> MovableListManage for ProxyTree<'a> {
             let step = if event.fast { 3 } else { 1 };
             let group = &mut self.groups[self.cursor];
             match event.code {
-                KeyCode::Up => {
-                    if group.cursor > 0 {
-                        group.cursor = group.cursor.saturating_sub(step)
-                    }
-                }
+                KeyCode::Up if group.cursor > 0 => group.cursor = group.cursor.saturating_sub(step),
                 KeyCode::Down => {
                     let left = group.members.len().saturating_sub(group.cursor + 1);
 
                     group.cursor += left.min(step)
                 }
-                KeyCode::Right | KeyCode::Enter => {
-                    if group.proxy_type.is_selector() {
-                        let current = group.members[group.cursor].name.to_owned();
-                        return Some(Action::ApplySelection {
-                            group: group.name.to_owned(),
-                            proxy: current,
-                        });
-                    }
+                KeyCode::Right | KeyCode::Enter if group.proxy_type.is_selector() => {
+                    let current = group.members[group.cursor].name.to_owned();
+                    return Some(Action::ApplySelection {
+                        group: group.name.to_owned(),
+                        proxy: current,
+                    });
                 }
                 _ => {}
             }
         } else {
             match event.code {
-                KeyCode::Up => {
-                    if self.cursor > 0 {
-                        self.cursor = self.cursor.saturating_sub(1)
-                    }
-                }
+                KeyCode::Up => self.cursor = self.next_visible(false, false).unwrap_or(self.cursor),
                 KeyCode::Down => {
-                    if self.cursor < self.groups.len() - 1 {
-                        self.cursor = self.cursor.saturating_add(1)
-                    }
+                    self.cursor = self.next_visible(true, false).unwrap_or(self.cursor)
                 }
                 KeyCode::Enter => self.expanded = true,
                 _ => {}
@@ -336,4 +384,25 @@ impl<'a> MovableListManage for ProxyTree<'a> {
             hold: false,
         }
     }
+
+    fn list_filter(&self) -> &ListFilter {
+        &self.filter
+    }
+
+    fn handle_filter(&mut self, event: FilterEvent) -> &mut Self {
+        if self.filter.handle(&event) {
+            if let Some(group) = self.groups.get(self.cursor) {
+                if !self.is_visible(group) {
+                    self.expanded = false;
+                    self.cursor = self.next_visible(true, true).unwrap_or(self.cursor);
+                }
+            }
+        }
+        match event {
+            FilterEvent::Next => self.jump(true),
+            FilterEvent::Prev => self.jump(false),
+            _ => {}
+        }
+        self.update_footer()
+    }
 }
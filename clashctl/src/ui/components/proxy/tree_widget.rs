@@ -19,21 +19,30 @@ impl<'a> ProxyTreeWidget<'a> {
 impl<'a> Widget for ProxyTreeWidget<'a> {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
         let cursor = &self.state.cursor;
-        let skip = if self.state.expanded {
-            *cursor
-        } else {
-            cursor.saturating_sub(2)
-        };
-        let text = self
+        let filter = &self.state.filter;
+        let groups = self
             .state
             .groups
             .iter()
-            .skip(skip)
             .enumerate()
+            .filter(|(_, x)| self.state.is_visible(x))
+            .collect::<Vec<_>>();
+        let pos = groups
+            .iter()
+            .position(|(i, _)| i == cursor)
+            .unwrap_or_default();
+        let skip = if self.state.expanded {
+            pos
+        } else {
+            pos.saturating_sub(2)
+        };
+        let text = groups
+            .into_iter()
+            .skip(skip)
             .map(|(i, x)| {
                 x.get_widget(
                     area.width as usize,
-                    match (self.state.expanded, *cursor == i + skip) {
+                    match (self.state.expanded, *cursor == i) {
                         (true, true) => ProxyGroupFocusStatus::Expanded,
                         (false, true) => ProxyGroupFocusStatus::Focused,
                         _ => ProxyGroupFocusStatus::None,
@@ -47,6 +56,7 @@ impl<'a> Widget for ProxyTreeWidget<'a> {
             .unwrap_or_default()
             .into_iter()
             .take(area.height as usize)
+            .map(|x| filter.highlight(x))
             .collect::<Vec<_>>();
 
         let block = if self.state.expanded {
@@ -107,11 +107,9 @@ impl<'a> Widget for Sparkline<'a> {
             .iter()
             .take(max_index)
             .map(|e| {
-                if max != 0 {
-                    e * u64::from(spark_area.height) * 8 / max
-                } else {
-                    0
-                }
+                (e * u64::from(spark_area.height) * 8)
+                    .checked_div(max)
+                    .unwrap_or(0)
             })
             .collect::<Vec<u64>>();
         for j in (0..spark_area.height).rev() {
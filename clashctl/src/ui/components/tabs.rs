@@ -1,10 +1,16 @@
+use clashctl_core::StreamState;
 use tui::{
     style::{Color, Modifier, Style},
     text::{Span, Spans},
     widgets::{Tabs as TuiTabs, Widget},
 };
 
-use crate::ui::{define_widget, utils::get_block, TuiStates};
+use crate::ui::{
+    components::{Footer, FooterItem, FooterWidget},
+    define_widget,
+    utils::get_block,
+    TuiStates, HMS,
+};
 
 define_widget!(Tabs);
 
@@ -34,6 +40,26 @@ impl<'a> Widget for Tabs<'a> {
                     .add_modifier(Modifier::BOLD),
             )
             .select(self.state.page_index.into());
-        tabs.render(area, buf)
+        tabs.render(area, buf);
+
+        if let Some((name, state)) = self.state.disconnected_stream() {
+            let banner = match state {
+                StreamState::Reconnecting { attempt, delay, .. } => format!(
+                    " {} disconnected, retry #{} in {} ",
+                    name,
+                    attempt,
+                    delay.hms()
+                ),
+                _ => format!(" {} disconnected ", name),
+            };
+            let mut footer = Footer::default();
+            footer.push_right(FooterItem::span(Span::styled(
+                banner,
+                Style::default()
+                    .fg(Color::Red)
+                    .add_modifier(Modifier::REVERSED),
+            )));
+            FooterWidget::new(&footer).render(area, buf);
+        }
     }
 }
@@ -1,9 +1,18 @@
-use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::{
+    collections::HashMap,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
 
 use once_cell::sync::OnceCell;
 use smart_default::SmartDefault;
 
-use crate::interactive::{clashctl::model::Config as ConfigModel, Config, ConfigData};
+use crate::{
+    interactive::{
+        clashctl::model::{Config as ConfigModel, ConfigPatch},
+        Config, ConfigData,
+    },
+    EditEvent,
+};
 
 // static CONFIG: OnceCell<RwLock<Config>> = OnceCell::new();
 static CONFIG: OnceCell<RwLock<Config>> = OnceCell::new();
@@ -30,20 +39,159 @@ pub fn get_config_mut<'a>() -> RwLockWriteGuard<'a, Config> {
         .unwrap()
 }
 
+/// Fields of clash config shown on the Configs page, in display order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConfigField {
+    Port,
+    SocksPort,
+    RedirPort,
+    TproxyPort,
+    MixedPort,
+    AllowLan,
+    Ipv6,
+    Mode,
+    LogLevel,
+    BindAddress,
+    Authentication,
+}
+
+impl ConfigField {
+    pub const ALL: [ConfigField; 11] = [
+        ConfigField::Port,
+        ConfigField::SocksPort,
+        ConfigField::RedirPort,
+        ConfigField::TproxyPort,
+        ConfigField::MixedPort,
+        ConfigField::AllowLan,
+        ConfigField::Ipv6,
+        ConfigField::Mode,
+        ConfigField::LogLevel,
+        ConfigField::BindAddress,
+        ConfigField::Authentication,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConfigField::Port => "Port",
+            ConfigField::SocksPort => "Socks Port",
+            ConfigField::RedirPort => "Redir Port",
+            ConfigField::TproxyPort => "Tproxy Port",
+            ConfigField::MixedPort => "Mixed Port",
+            ConfigField::AllowLan => "Allow Lan",
+            ConfigField::Ipv6 => "Ipv6",
+            ConfigField::Mode => "Mode",
+            ConfigField::LogLevel => "Log Level",
+            ConfigField::BindAddress => "Bind Address",
+            ConfigField::Authentication => "Authentication",
+        }
+    }
+
+    pub fn is_port(&self) -> bool {
+        matches!(
+            self,
+            ConfigField::Port
+                | ConfigField::SocksPort
+                | ConfigField::RedirPort
+                | ConfigField::TproxyPort
+                | ConfigField::MixedPort
+        )
+    }
+
+    pub fn is_editable(&self) -> bool {
+        !matches!(self, ConfigField::BindAddress | ConfigField::Authentication)
+    }
+
+    pub fn value(&self, config: &ConfigModel) -> String {
+        match self {
+            ConfigField::Port => config.port.to_string(),
+            ConfigField::SocksPort => config.socks_port.to_string(),
+            ConfigField::RedirPort => config.redir_port.to_string(),
+            ConfigField::TproxyPort => config.tproxy_port.to_string(),
+            ConfigField::MixedPort => config.mixed_port.to_string(),
+            ConfigField::AllowLan => config.allow_lan.to_string(),
+            ConfigField::Ipv6 => config.ipv6.to_string(),
+            ConfigField::Mode => config.mode.to_string(),
+            ConfigField::LogLevel => config.log_level.to_string(),
+            ConfigField::BindAddress => config.bind_address.to_string(),
+            ConfigField::Authentication => config.authentication.len().to_string(),
+        }
+    }
+
+    /// Value of this field requested by `patch`, formatted like [`value`]
+    ///
+    /// [`value`]: ConfigField::value
+    pub fn requested(&self, patch: &ConfigPatch) -> Option<String> {
+        match self {
+            ConfigField::Port => patch.port.map(|x| x.to_string()),
+            ConfigField::SocksPort => patch.socks_port.map(|x| x.to_string()),
+            ConfigField::RedirPort => patch.redir_port.map(|x| x.to_string()),
+            ConfigField::TproxyPort => patch.tproxy_port.map(|x| x.to_string()),
+            ConfigField::MixedPort => patch.mixed_port.map(|x| x.to_string()),
+            ConfigField::AllowLan => patch.allow_lan.map(|x| x.to_string()),
+            ConfigField::Ipv6 => patch.ipv6.map(|x| x.to_string()),
+            ConfigField::Mode => patch.mode.map(|x| x.to_string()),
+            ConfigField::LogLevel => patch.log_level.as_ref().map(|x| x.to_string()),
+            ConfigField::BindAddress | ConfigField::Authentication => None,
+        }
+    }
+
+    /// Patch that toggles booleans and cycles enums. `None` for other fields
+    pub fn next_value(&self, config: &ConfigModel) -> Option<ConfigPatch> {
+        let mut patch = ConfigPatch::default();
+        match self {
+            ConfigField::AllowLan => patch.allow_lan = Some(!config.allow_lan),
+            ConfigField::Ipv6 => patch.ipv6 = Some(!config.ipv6),
+            ConfigField::Mode => patch.mode = Some(config.mode.next()),
+            ConfigField::LogLevel => patch.log_level = Some(config.log_level.next()),
+            _ => return None,
+        }
+        Some(patch)
+    }
+
+    /// Patch that sets port to `input`, if this is a port field and `input`
+    /// is a valid port
+    pub fn port_value(&self, input: &str) -> Result<ConfigPatch, String> {
+        let port = input
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid port {:?}", input))? as u64;
+        let mut patch = ConfigPatch::default();
+        match self {
+            ConfigField::Port => patch.port = Some(port),
+            ConfigField::SocksPort => patch.socks_port = Some(port),
+            ConfigField::RedirPort => patch.redir_port = Some(port),
+            ConfigField::TproxyPort => patch.tproxy_port = Some(port),
+            ConfigField::MixedPort => patch.mixed_port = Some(port),
+            _ => return Err(format!("{} is not a port", self.label())),
+        }
+        Ok(patch)
+    }
+}
+
+/// Whether a change of config field is accepted by clash
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldStatus {
+    Pending,
+    Applied,
+    Failed(String),
+}
+
 #[derive(Clone, Debug, SmartDefault)]
 pub struct ConfigState {
     clash: Option<ConfigModel>,
     #[default(_code = "{ get_config().get_inner().clone() }")]
     clashctl: ConfigData,
-    // offset: usize,
+    /// Index of selected field in [`ConfigField::ALL`]
+    selected: usize,
+    /// Input of the port being edited
+    input: Option<String>,
+    status: HashMap<ConfigField, FieldStatus>,
 }
 
 impl ConfigState {
     pub fn clashctl_list(&self) -> impl Iterator<Item = (&str, String)> {
         let server = self
             .clashctl
-            .using
-            .as_ref()
+            .using_server()
             .map(|x| x.to_string())
             .unwrap_or_else(|| "N/A".to_owned());
         let log_dir = self
@@ -57,40 +205,178 @@ impl ConfigState {
         [("Server", server), ("Log dir", log_dir)].into_iter()
     }
 
-    pub fn clash_list(&self) -> impl Iterator<Item = (&str, String)> {
-        match self.clash {
-            Some(ref conf) => vec![
-                ("Port", conf.port.to_string()),
-                ("Socks Port", conf.socks_port.to_string()),
-                ("Redir Port", conf.redir_port.to_string()),
-                ("Tproxy Port", conf.tproxy_port.to_string()),
-                ("Mixed Port", conf.mixed_port.to_string()),
-                ("Allow Lan", conf.allow_lan.to_string()),
-                ("Ipv6", conf.ipv6.to_string()),
-                ("Mode", conf.mode.to_string()),
-                ("Log Level", conf.log_level.to_string()),
-                ("Bind_Address", conf.bind_address.to_string()),
-                ("Authentication", conf.authentication.len().to_string()),
-            ]
-            .into_iter(),
-            None => vec![
-                ("Port", NONE.into()),
-                ("Socks Port", NONE.into()),
-                ("Redir Port", NONE.into()),
-                ("Tproxy Port", NONE.into()),
-                ("Mixed Port", NONE.into()),
-                ("Allow Lan", NONE.into()),
-                ("Ipv6", NONE.into()),
-                ("Mode", NONE.into()),
-                ("Log Level", NONE.into()),
-                ("Bind Address", NONE.into()),
-                ("Authentication", NONE.into()),
-            ]
-            .into_iter(),
-        }
+    pub fn clash_list(&self) -> impl Iterator<Item = (ConfigField, String)> + '_ {
+        ConfigField::ALL.into_iter().map(|field| {
+            let value = match self.clash {
+                Some(ref conf) => field.value(conf),
+                None => NONE.into(),
+            };
+            (field, value)
+        })
+    }
+
+    pub fn clash(&self) -> Option<&ConfigModel> {
+        self.clash.as_ref()
     }
 
     pub fn update_clash(&mut self, config: ConfigModel) {
         self.clash = Some(config)
     }
+
+    pub fn selected(&self) -> ConfigField {
+        ConfigField::ALL[self.selected]
+    }
+
+    pub fn input(&self) -> Option<&str> {
+        self.input.as_deref()
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn status(&self, field: ConfigField) -> Option<&FieldStatus> {
+        self.status.get(&field)
+    }
+
+    pub fn select(&mut self, forward: bool) {
+        let len = ConfigField::ALL.len();
+        self.selected = if forward {
+            (self.selected + 1).min(len - 1)
+        } else {
+            self.selected.saturating_sub(1)
+        };
+    }
+
+    /// Toggle or cycle `field`, or start editing it if it's a port
+    pub fn edit(&mut self, field: ConfigField) -> Option<ConfigPatch> {
+        let config = self.clash.as_ref()?;
+        if field.is_port() {
+            self.input = Some(field.value(config));
+            return None;
+        }
+        let patch = field.next_value(config)?;
+        self.request(&patch);
+        Some(patch)
+    }
+
+    pub fn handle_edit(&mut self, event: EditEvent) -> Option<ConfigPatch> {
+        let input = self.input.as_mut()?;
+        match event {
+            EditEvent::Push(char) => {
+                if char.is_ascii_digit() {
+                    input.push(char)
+                }
+            }
+            EditEvent::Pop => {
+                input.pop();
+            }
+            EditEvent::Cancel => self.input = None,
+            EditEvent::Confirm => {
+                let field = self.selected();
+                let input = self.input.take()?;
+                match field.port_value(&input) {
+                    Ok(patch) => {
+                        self.request(&patch);
+                        return Some(patch);
+                    }
+                    Err(e) => {
+                        self.status.insert(field, FieldStatus::Failed(e));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Mark fields in `patch` as applied or failed. Called after configs are
+    /// updated with the result of the patch
+    pub fn patched(&mut self, patch: &ConfigPatch, error: Option<String>) {
+        for field in ConfigField::ALL {
+            let requested = match field.requested(patch) {
+                Some(requested) => requested,
+                None => continue,
+            };
+            let status = match (&error, &self.clash) {
+                (Some(e), _) => FieldStatus::Failed(e.to_owned()),
+                (None, Some(conf)) if field.value(conf) == requested => FieldStatus::Applied,
+                (None, _) => FieldStatus::Failed("Not accepted by clash".to_owned()),
+            };
+            self.status.insert(field, status);
+        }
+    }
+
+    fn request(&mut self, patch: &ConfigPatch) {
+        for field in ConfigField::ALL {
+            if field.requested(patch).is_some() {
+                self.status.insert(field, FieldStatus::Pending);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_config_state_edit() {
+    use crate::interactive::clashctl::model::{Level, Mode};
+
+    let mut state = ConfigState {
+        clash: Some(ConfigModel {
+            port: 7890,
+            socks_port: 7891,
+            redir_port: 0,
+            tproxy_port: 0,
+            mixed_port: 0,
+            allow_lan: false,
+            ipv6: false,
+            mode: Mode::Rule,
+            log_level: Level::Info,
+            bind_address: "*".to_owned(),
+            authentication: vec![],
+        }),
+        clashctl: ConfigData::default(),
+        selected: 0,
+        input: None,
+        status: HashMap::new(),
+    };
+
+    // Ports are edited with validation
+    assert_eq!(state.edit(ConfigField::Port), None);
+    assert_eq!(state.input(), Some("7890"));
+    for event in [EditEvent::Pop, EditEvent::Push('x'), EditEvent::Push('2')] {
+        state.handle_edit(event);
+    }
+    let patch = state.handle_edit(EditEvent::Confirm).unwrap();
+    assert_eq!(patch.port, Some(7892));
+    assert!(!state.is_editing());
+    assert_eq!(state.status(ConfigField::Port), Some(&FieldStatus::Pending));
+
+    // Clash ignored the change
+    state.patched(&patch, None);
+    assert!(matches!(
+        state.status(ConfigField::Port),
+        Some(FieldStatus::Failed(_))
+    ));
+
+    state.edit(ConfigField::Port);
+    state.handle_edit(EditEvent::Push('0'));
+    assert!(state.handle_edit(EditEvent::Confirm).is_none());
+    assert_eq!(
+        state.status(ConfigField::Port),
+        Some(&FieldStatus::Failed("Invalid port \"78900\"".to_owned()))
+    );
+
+    // Booleans and enums change at once
+    let patch = state.edit(ConfigField::Mode).unwrap();
+    assert_eq!(patch.mode, Some(Mode::Direct));
+    state.clash.as_mut().unwrap().mode = Mode::Direct;
+    state.patched(&patch, None);
+    assert_eq!(state.status(ConfigField::Mode), Some(&FieldStatus::Applied));
+
+    let patch = state.edit(ConfigField::AllowLan).unwrap();
+    state.patched(&patch, Some("Unauthorized".to_owned()));
+    assert_eq!(
+        state.status(ConfigField::AllowLan),
+        Some(&FieldStatus::Failed("Unauthorized".to_owned()))
+    );
+    assert_eq!(state.edit(ConfigField::BindAddress), None);
 }
@@ -1,6 +1,9 @@
 use std::fmt::Display;
 
-use clashctl_core::model::{ConnectionsWithSpeed, Log, Proxies, Rules, Traffic, Version};
+use clashctl_core::{
+    model::{ConfigPatch, Log, Providers, Proxies, Rules, Traffic, Version},
+    StreamState, TrackedConnections,
+};
 use crossterm::event::{KeyCode as KC, KeyEvent as KE, KeyModifiers as KM};
 use log::Level;
 use tui::{
@@ -17,6 +20,9 @@ use crate::{
 #[non_exhaustive]
 pub enum Event {
     Quit,
+    /// Raw key press, mapped into other events by `TuiStates` depending on
+    /// current mode, e.g. whether a filter is being edited
+    Key(KE),
     Action(Action),
     Input(InputEvent),
     Update(UpdateEvent),
@@ -27,6 +33,10 @@ impl<'a> MovableListItem<'a> for Event {
     fn to_spans(&self) -> Spans<'a> {
         match self {
             Event::Quit => Spans(vec![]),
+            Event::Key(key) => Spans(vec![
+                Span::styled("✜  ", Style::default().fg(Color::Green)),
+                Span::raw(format!("{:?}", key)),
+            ]),
             Event::Action(action) => Spans(vec![
                 Span::styled("⋉ ", Style::default().fg(Color::Yellow)),
                 Span::raw(format!("{:?}", action)),
@@ -68,6 +78,16 @@ impl Event {
     pub fn is_diagnostic(&self) -> bool {
         matches!(self, Event::Diagnostic(_))
     }
+
+    /// Map keys while a confirmation prompt is shown
+    pub fn from_confirming(value: KE) -> Self {
+        match (value.modifiers, value.code) {
+            (KM::CONTROL, KC::Char('c')) => Self::Quit,
+            (_, KC::Enter | KC::Char('y' | 'Y')) => Self::Input(InputEvent::Confirm),
+            (_, KC::Esc | KC::Char('n' | 'N')) => Self::Input(InputEvent::Cancel),
+            _ => Self::Input(InputEvent::Other(value)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -81,6 +101,15 @@ pub enum InputEvent {
     TestLatency,
     NextSort,
     PrevSort,
+    Filter(FilterEvent),
+    CloseConnection,
+    CloseConnections,
+    CycleMode,
+    MatchRule,
+    UpdateProvider,
+    Edit(EditEvent),
+    Confirm,
+    Cancel,
     Other(KE),
 }
 
@@ -90,16 +119,78 @@ pub struct ListEvent {
     pub code: KC,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterEvent {
+    /// Start editing a new query
+    Start,
+    Push(char),
+    Pop,
+    /// Stop editing and keep the query
+    Confirm,
+    /// Stop editing and drop the query
+    Clear,
+    /// Jump to next match
+    Next,
+    /// Jump to previous match
+    Prev,
+}
+
+impl FilterEvent {
+    /// Map keys while a filter query is being edited
+    pub fn from_editing(value: KE) -> Option<Self> {
+        match (value.modifiers, value.code) {
+            (_, KC::Esc) => Some(Self::Clear),
+            (_, KC::Enter) => Some(Self::Confirm),
+            (_, KC::Backspace) => Some(Self::Pop),
+            (KM::NONE | KM::SHIFT, KC::Char(char)) => Some(Self::Push(char)),
+            _ => None,
+        }
+    }
+}
+
+/// Editing a value in place, e.g. a port on Configs page or the target to
+/// match on Rules page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditEvent {
+    Push(char),
+    Pop,
+    Confirm,
+    Cancel,
+}
+
+impl EditEvent {
+    /// Map keys while a value is being edited
+    pub fn from_editing(value: KE) -> Option<Self> {
+        match (value.modifiers, value.code) {
+            (_, KC::Esc) => Some(Self::Cancel),
+            (_, KC::Enter) => Some(Self::Confirm),
+            (_, KC::Backspace) => Some(Self::Pop),
+            (KM::NONE | KM::SHIFT, KC::Char(char)) => Some(Self::Push(char)),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum UpdateEvent {
     Config(crate::interactive::clashctl::model::Config),
-    Connection(ConnectionsWithSpeed),
+    Connection(TrackedConnections),
     Version(Version),
     Traffic(Traffic),
     Proxies(Proxies),
+    Providers(Providers),
     Rules(Rules),
     Log(Log),
+    TrafficStream(StreamState),
+    LogStream(StreamState),
+    ConnectionStream(StreamState),
+    ConnectionsClosed(Vec<String>),
+    /// Result of [`Action::PatchConfig`], sent after configs are updated
+    ConfigPatched {
+        patch: ConfigPatch,
+        error: Option<String>,
+    },
     ProxyTestLatencyDone,
 }
 
@@ -111,8 +202,17 @@ impl Display for UpdateEvent {
             UpdateEvent::Version(x) => write!(f, "{:?}", x),
             UpdateEvent::Traffic(x) => write!(f, "{:?}", x),
             UpdateEvent::Proxies(x) => write!(f, "{:?}", x),
+            UpdateEvent::Providers(x) => write!(f, "{:?}", x),
             UpdateEvent::Rules(x) => write!(f, "{:?}", x),
             UpdateEvent::Log(x) => write!(f, "{:?}", x),
+            UpdateEvent::TrafficStream(x) => write!(f, "Traffic stream {:?}", x),
+            UpdateEvent::LogStream(x) => write!(f, "Log stream {:?}", x),
+            UpdateEvent::ConnectionStream(x) => write!(f, "Connection stream {:?}", x),
+            UpdateEvent::ConnectionsClosed(x) => write!(f, "Closed connections {:?}", x),
+            UpdateEvent::ConfigPatched { patch, error } => match error {
+                Some(e) => write!(f, "Failed to patch configs {:?}: {}", patch, e),
+                None => write!(f, "Patched configs {:?}", patch),
+            },
             UpdateEvent::ProxyTestLatencyDone => write!(f, "Test latency done"),
         }
     }
@@ -156,6 +256,16 @@ impl From<KE> for Event {
             }
             (KM::ALT, KC::Char('s')) => Self::Input(InputEvent::PrevSort),
             (KM::NONE, KC::Char('s')) => Self::Input(InputEvent::NextSort),
+            (KM::NONE, KC::Char('/')) => Self::Input(InputEvent::Filter(FilterEvent::Start)),
+            (KM::NONE, KC::Char('n')) => Self::Input(InputEvent::Filter(FilterEvent::Next)),
+            (KM::NONE | KM::SHIFT, KC::Char('N')) => {
+                Self::Input(InputEvent::Filter(FilterEvent::Prev))
+            }
+            (KM::NONE, KC::Char('d')) => Self::Input(InputEvent::CloseConnection),
+            (KM::NONE | KM::SHIFT, KC::Char('D')) => Self::Input(InputEvent::CloseConnections),
+            (KM::NONE, KC::Char('m')) => Self::Input(InputEvent::CycleMode),
+            (KM::NONE | KM::SHIFT, KC::Char('?')) => Self::Input(InputEvent::MatchRule),
+            (KM::NONE, KC::Char('u')) => Self::Input(InputEvent::UpdateProvider),
             (KM::NONE, key_code) => key_code
                 .try_into()
                 .unwrap_or(Self::Input(InputEvent::Other(value))),
@@ -1,10 +1,14 @@
 use tui::{
     layout::Rect,
     style::{Color, Modifier, Style},
+    text::{Span, Spans},
     widgets::{List, ListItem, Widget},
 };
 
-use crate::{get_block, ConfigState};
+use crate::{
+    components::{Footer, FooterItem, FooterWidget},
+    get_block, ConfigState, FieldStatus,
+};
 
 #[derive(Clone, Debug)]
 pub struct ConfigPage<'a> {
@@ -19,13 +23,18 @@ impl<'a> ConfigPage<'a> {
 
 enum ConfigListItem<'a> {
     Title(&'a str),
-    Item { label: &'a str, content: String },
+    Item {
+        label: &'a str,
+        content: String,
+        status: Option<Span<'a>>,
+        selected: bool,
+    },
     Separator,
     Empty,
 }
 
 impl<'a> ConfigListItem<'a> {
-    pub fn title(title: &'a str) -> impl Iterator<Item = ConfigListItem> {
+    pub fn title(title: &'a str) -> impl Iterator<Item = ConfigListItem<'a>> {
         [
             ConfigListItem::Empty,
             ConfigListItem::Title(title),
@@ -41,13 +50,27 @@ impl<'a> ConfigListItem<'a> {
                     .fg(Color::Green)
                     .add_modifier(Modifier::BOLD),
             ),
-            ConfigListItem::Item { label, content } => ListItem::new(format!(
-                "{:<15}{:>right$}",
+            ConfigListItem::Item {
                 label,
                 content,
-                right = (width - 15) as usize
-            ))
-            .style(Style::default().fg(Color::White)),
+                status,
+                selected,
+            } => {
+                let status_width = status.as_ref().map_or(0, |x| x.width());
+                let mut spans = vec![Span::raw(format!(
+                    "{:<15}{:>right$}",
+                    label,
+                    content,
+                    right = (width as usize).saturating_sub(15 + status_width)
+                ))];
+                spans.extend(status);
+                let style = Style::default().fg(Color::White);
+                ListItem::new(Spans(spans)).style(if selected {
+                    style.add_modifier(Modifier::REVERSED)
+                } else {
+                    style
+                })
+            }
             ConfigListItem::Separator => {
                 ListItem::new(format!("{:-<width$}", "", width = width as usize))
             }
@@ -63,15 +86,41 @@ impl<'a> Widget for ConfigPage<'a> {
         let width = area.width.saturating_sub(4).max(10);
         let block = get_block("Config");
         let list = ConfigListItem::title("Clash")
-            .chain(self.state.clash_list().map(|x| ConfigListItem::Item {
-                label: x.0,
-                content: x.1,
+            .chain(self.state.clash_list().map(|(field, value)| {
+                let selected = field == self.state.selected();
+                let content = match self.state.input() {
+                    Some(input) if selected => format!("{}▏", input),
+                    _ => value,
+                };
+                let status = self.state.status(field).map(|status| match status {
+                    FieldStatus::Pending => {
+                        Span::styled(" … pending", Style::default().fg(Color::Yellow))
+                    }
+                    FieldStatus::Applied => {
+                        Span::styled(" ✓ applied", Style::default().fg(Color::Green))
+                    }
+                    FieldStatus::Failed(e) => {
+                        Span::styled(format!(" ✗ {}", e), Style::default().fg(Color::Red))
+                    }
+                });
+                ConfigListItem::Item {
+                    label: field.label(),
+                    content,
+                    status,
+                    selected,
+                }
             }))
             .chain(ConfigListItem::title("Clashctl"))
-            .chain(self.state.clashctl_list().map(|x| ConfigListItem::Item {
-                label: x.0,
-                content: x.1,
-            }))
+            .chain(
+                self.state
+                    .clashctl_list()
+                    .map(|(label, content)| ConfigListItem::Item {
+                        label,
+                        content,
+                        status: None,
+                        selected: false,
+                    }),
+            )
             .map(|x| x.into_list_item(width))
             .collect::<Vec<_>>();
         let inner = block.inner(area);
@@ -82,5 +131,24 @@ impl<'a> Widget for ConfigPage<'a> {
         };
         block.render(area, buf);
         List::new(list).render(inner, buf);
+
+        let mut footer = Footer::default();
+        let style = Style::default()
+            .fg(Color::Blue)
+            .add_modifier(Modifier::REVERSED);
+        if self.state.is_editing() {
+            footer
+                .push_left(FooterItem::span(Span::styled(" EDIT ", style)))
+                .push_left(FooterItem::span(Span::styled(
+                    " [Enter] Apply [Esc] Cancel ",
+                    style,
+                )));
+        } else {
+            footer
+                .push_left(FooterItem::span(Span::styled(" ▲ ▼ Select ", style)))
+                .push_left(FooterItem::span(Span::styled(" [Enter] Edit ", style)))
+                .push_left(FooterItem::span(Span::styled(" [m] Cycle mode ", style)));
+        }
+        FooterWidget::new(&footer).render(area, buf);
     }
 }
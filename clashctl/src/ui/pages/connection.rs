@@ -1,15 +1,20 @@
 use bytesize::ByteSize;
-use chrono::Utc;
+use chrono::{Local, Utc};
 use tui::{
+    layout::{Constraint, Layout},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::Widget,
+    widgets::{Clear, Paragraph, Widget, Wrap},
 };
 
 use crate::{
-    components::{MovableList, MovableListItem},
+    components::{MovableList, MovableListItem, Sparkline, DOTS, HALF, REV_DOTS},
     define_widget,
     interactive::clashctl::model::ConnectionWithSpeed,
+    ui::{
+        utils::{centered_rect, get_focused_block},
+        ConnectionDetail,
+    },
     HMS,
 };
 
@@ -18,6 +23,109 @@ define_widget!(ConnectionPage);
 impl<'a> Widget for ConnectionPage<'a> {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
         MovableList::new("Connections", &self.state.con_state).render(area, buf);
+        if let Some(ref detail) = self.state.con_detail {
+            ConnectionDetailPopup { detail }.render(area, buf);
+        }
+    }
+}
+
+struct ConnectionDetailPopup<'a> {
+    detail: &'a ConnectionDetail,
+}
+
+impl<'a> Widget for ConnectionDetailPopup<'a> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let ConnectionWithSpeed {
+            connection: con,
+            upload,
+            download,
+        } = &self.detail.connection;
+        let meta = &con.metadata;
+        let label = Style::default().fg(Color::DarkGray);
+        let line = |name: &'static str, value: String| {
+            Spans(vec![
+                Span::styled(format!("{:<13}", name), label),
+                Span::raw(value),
+            ])
+        };
+        let size = |total: u64, speed: &Option<u64>| {
+            format!(
+                "{}  ({}/s)",
+                ByteSize(total).to_string_as(true),
+                ByteSize(speed.unwrap_or_default()).to_string_as(true)
+            )
+        };
+
+        let text = vec![
+            line("ID", con.id.to_owned()),
+            line("Host", meta.host.to_owned()),
+            line(
+                "Network",
+                format!("{} / {}", meta.network, meta.connection_type),
+            ),
+            line("Source", format!("{}:{}", meta.source_ip, meta.source_port)),
+            line(
+                "Destination",
+                format!("{}:{}", meta.destination_ip, meta.destination_port),
+            ),
+            line("Rule", con.rule.to_string()),
+            line("Rule payload", con.rule_payload.to_owned()),
+            line("Chains", con.chains.join(" - ")),
+            line(
+                "Start",
+                format!(
+                    "{} ({} ago)",
+                    con.start.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+                    (Utc::now() - con.start).hms()
+                ),
+            ),
+            line("Download", size(con.download, download)),
+            line("Upload", size(con.upload, upload)),
+        ];
+
+        let area = centered_rect(
+            area.width.saturating_sub(4).min(100),
+            area.height.saturating_sub(2).min(text.len() as u16 + 8),
+            area,
+        );
+        let block = get_focused_block(if self.detail.closed {
+            "Connection (closed)"
+        } else {
+            "Connection"
+        });
+        let inner = block.inner(area);
+        let layout = Layout::default()
+            .constraints([Constraint::Min(0), Constraint::Length(4)])
+            .split(inner);
+        let sparkline = Layout::default().constraints([HALF, HALF]).split(layout[1]);
+
+        let (up, down): (Vec<_>, Vec<_>) = self
+            .detail
+            .speeds
+            .iter()
+            .rev()
+            .take(inner.width.into())
+            .copied()
+            .unzip();
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .render(layout[0], buf);
+        Sparkline::default()
+            .data(&up)
+            .max(up.iter().copied().max().unwrap_or_default().max(1))
+            .bar_set(DOTS)
+            .style(Style::default().fg(Color::Green))
+            .render(sparkline[0], buf);
+        Sparkline::default()
+            .data(&down)
+            .max(down.iter().copied().max().unwrap_or_default().max(1))
+            .bar_set(REV_DOTS)
+            .style(Style::default().fg(Color::White))
+            .reversed(true)
+            .render(sparkline[1], buf);
     }
 }
 
@@ -44,7 +152,7 @@ impl<'a> MovableListItem<'a> for ConnectionWithSpeed {
             } else {
                 &meta.destination_ip
             },
-            meta.source_port
+            meta.destination_port
         );
         let dash: String = "─".repeat(44_usize.saturating_sub(src.len() + dest.len()).max(1));
 
@@ -1,11 +1,11 @@
 use tui::{
-    style::Style,
+    style::{Color, Modifier, Style},
     text::{Span, Spans},
     widgets::Widget,
 };
 
 use crate::{
-    clashctl::model::Log,
+    clashctl::model::{ConnectionLog, Log, LogPayload},
     components::{MovableList, MovableListItem},
     define_widget, AsColor,
 };
@@ -13,20 +13,66 @@ use crate::{
 impl<'a> MovableListItem<'a> for Log {
     fn to_spans(&self) -> Spans<'a> {
         let color = self.log_type.clone().as_color();
-        Spans::from(vec![
+        let dimmed = Style::default().fg(Color::DarkGray);
+        let mut spans = vec![
             Span::styled(
                 format!("{:<5}", self.log_type.to_string().to_uppercase()),
                 Style::default().fg(color),
             ),
             Span::raw(" "),
-            Span::raw(self.payload.to_owned()),
-        ])
+        ];
+        match self.parse() {
+            LogPayload::Connection(ConnectionLog {
+                network,
+                source,
+                destination,
+                rule,
+                rule_payload,
+                proxy,
+            }) => {
+                let network_color = match network.as_str() {
+                    "TCP" => Color::Cyan,
+                    _ => Color::Magenta,
+                };
+                let rule = match rule {
+                    Some(rule) if rule_payload.is_empty() => rule,
+                    Some(rule) => format!("{}({})", rule, rule_payload),
+                    None => "-".to_owned(),
+                };
+                spans.extend([
+                    Span::styled(
+                        format!("{:<4}", network),
+                        Style::default().fg(network_color),
+                    ),
+                    Span::raw(format!("{:<22}", source)),
+                    Span::styled(" ⇢  ", dimmed),
+                    Span::styled(
+                        format!("{:<45}", destination),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(" ✤  ", dimmed),
+                    Span::styled(format!("{:<40}", rule), Style::default().fg(Color::Yellow)),
+                    Span::styled(" ⟴  ", dimmed),
+                    Span::styled(proxy, Style::default().fg(Color::Green)),
+                ])
+            }
+            LogPayload::Dns(dns) => spans.extend([
+                Span::styled(format!("{:<4}", "DNS"), Style::default().fg(Color::Blue)),
+                Span::raw(dns),
+            ]),
+            LogPayload::Error { context, message } => spans.extend([
+                Span::styled(context, dimmed),
+                Span::styled(" ✗  ", dimmed),
+                Span::styled(message, Style::default().fg(Color::Red)),
+            ]),
+            LogPayload::Other(payload) => spans.push(Span::raw(payload)),
+        }
+        Spans::from(spans)
     }
 }
 
 define_widget!(LogPage);
 
-// TODO Pretty print parsed Log
 impl<'a> Widget for LogPage<'a> {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
         let list = MovableList::new("Logs", &self.state.log_state);
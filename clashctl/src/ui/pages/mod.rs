@@ -2,6 +2,7 @@ mod config;
 mod connection;
 mod debug;
 mod log;
+mod provider;
 mod proxy;
 mod rule;
 mod status;
@@ -18,7 +19,8 @@ pub fn route(state: &TuiStates, area: Rect, f: &mut Frame<Backend>) {
         3 => f.render_widget(connection::ConnectionPage::new(state), area),
         4 => f.render_widget(log::LogPage::new(state), area),
         5 => f.render_widget(config::ConfigPage::new(&state.config_state), area),
-        6 => f.render_widget(debug::DebugPage::new(state), area),
+        6 => f.render_widget(provider::ProviderPage::new(state), area),
+        7 => f.render_widget(debug::DebugPage::new(state), area),
         _ => unreachable!(),
     };
 }
@@ -0,0 +1,181 @@
+use bytesize::ByteSize;
+use chrono::{Local, TimeZone, Utc};
+use clashctl_core::model::{Provider, ProviderProxy, SubscriptionInfo, VehicleType};
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::{
+    components::{Consts, MovableList, MovableListItem, ProxyGroup},
+    define_widget,
+    ui::utils::{centered_rect, get_focused_block},
+    AsColor, HMS,
+};
+
+define_widget!(ProviderPage);
+
+impl<'a> Widget for ProviderPage<'a> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        MovableList::new("Providers", &self.state.provider_state).render(area, buf);
+        if self.state.provider_detail.is_some() {
+            if let Some(provider) = self.state.selected_provider() {
+                ProviderDetailPopup { provider }.render(area, buf);
+            }
+        }
+    }
+}
+
+struct ProviderDetailPopup<'a> {
+    provider: &'a Provider,
+}
+
+impl<'a> Widget for ProviderDetailPopup<'a> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let provider = self.provider;
+        let label = Style::default().fg(Color::DarkGray);
+        let line = |name: &'static str, value: String| {
+            Spans(vec![
+                Span::styled(format!("{:<13}", name), label),
+                Span::raw(value),
+            ])
+        };
+
+        let mut text = vec![
+            line("Type", provider.vehicle_type.to_string()),
+            line("Updated", updated(provider)),
+        ];
+        if let Some(ref info) = provider.subscription_info {
+            text.push(line("Quota", quota(info)));
+            text.push(line("Download", ByteSize(info.download).to_string_as(true)));
+            text.push(line("Upload", ByteSize(info.upload).to_string_as(true)));
+            text.push(line("Expire", expire(info)));
+        }
+        text.push(Spans::default());
+        text.extend(provider.proxies.iter().map(proxy_spans));
+        text.push(Spans::default());
+        text.push(Spans::from(Span::styled(
+            "[t] Healthcheck [u] Update [Esc] Close",
+            label,
+        )));
+
+        let area = centered_rect(
+            area.width.saturating_sub(4).min(100),
+            area.height.saturating_sub(2).min(text.len() as u16 + 2),
+            area,
+        );
+        Clear.render(area, buf);
+        Paragraph::new(text)
+            .block(get_focused_block(&provider.name))
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
+
+fn proxy_spans<'a>(proxy: &ProviderProxy) -> Spans<'a> {
+    let delay = match proxy.history.first() {
+        Some(history) if history.delay > 0 => Span::styled(
+            format!("{:<8}", history.delay),
+            ProxyGroup::get_delay_style(history.delay),
+        ),
+        _ => Span::styled(format!("{:<8}", "-"), Consts::NO_LATENCY_STYLE),
+    };
+    Spans(vec![
+        Span::styled(
+            format!("{:<16}", proxy.proxy_type.to_string()),
+            Style::default().fg(Color::DarkGray),
+        ),
+        delay,
+        Span::raw(proxy.name.to_owned()),
+    ])
+}
+
+fn updated(provider: &Provider) -> String {
+    match provider.updated_at {
+        Some(time) => format!(
+            "{} ({} ago)",
+            time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+            (Utc::now() - time).hms()
+        ),
+        None => "-".to_owned(),
+    }
+}
+
+fn quota(info: &SubscriptionInfo) -> String {
+    let used = ByteSize(info.used()).to_string_as(true);
+    match info.total {
+        0 => format!("{} / ∞", used),
+        total => format!(
+            "{} / {} ({:.1}%)",
+            used,
+            ByteSize(total).to_string_as(true),
+            info.used() as f64 / total as f64 * 100.
+        ),
+    }
+}
+
+fn expire(info: &SubscriptionInfo) -> String {
+    match Utc.timestamp_opt(info.expire as i64, 0).single() {
+        Some(time) if info.expire != 0 => format!(
+            "{} ({} left)",
+            time.with_timezone(&Local).format("%Y-%m-%d"),
+            (time - Utc::now()).hms()
+        ),
+        _ => "Never".to_owned(),
+    }
+}
+
+impl AsColor for VehicleType {
+    fn as_color(&self) -> Color {
+        match self {
+            VehicleType::Http => Color::Green,
+            VehicleType::File => Color::Yellow,
+            VehicleType::Compatible => Color::Blue,
+            VehicleType::Unknown => Color::DarkGray,
+        }
+    }
+}
+
+impl<'a> MovableListItem<'a> for Provider {
+    fn to_spans(&self) -> Spans<'a> {
+        let dimmed = Style::default().fg(Color::DarkGray);
+        let mut spans = vec![
+            Span::styled(
+                format!("{:<24}", self.name),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{:<12}", self.vehicle_type.to_string()),
+                Style::default().fg(self.vehicle_type.as_color()),
+            ),
+            Span::raw(format!("{:>4} ", self.proxies.len())),
+            Span::styled("proxies ", dimmed),
+            Span::styled(" ⏲  ", dimmed),
+            Span::raw(format!(
+                "{:<12}",
+                self.updated_at
+                    .map(|time| format!("{} ago", (Utc::now() - time).hms()))
+                    .unwrap_or_else(|| "-".to_owned())
+            )),
+        ];
+        if let Some(ref info) = self.subscription_info {
+            spans.extend([
+                Span::styled(" ⇅  ", dimmed),
+                Span::raw(format!("{:<32}", quota(info))),
+            ]);
+        }
+        spans.push(Span::raw(" "));
+        spans.extend(self.proxies.iter().map(|x| {
+            if !x.proxy_type.is_normal() {
+                Consts::NOT_PROXY_SPAN
+            } else {
+                match x.history.first() {
+                    Some(history) => ProxyGroup::get_delay_span(history.delay),
+                    None => Consts::NO_LATENCY_SPAN,
+                }
+            }
+        }));
+        spans.into()
+    }
+}
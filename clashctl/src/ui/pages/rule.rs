@@ -1,15 +1,24 @@
-use clashctl_core::model::{Rule, RuleType, Rules};
+use bytesize::ByteSize;
+use chrono::Utc;
+use clashctl_core::{
+    model::{Rule, RuleType, RuleWithStats, Rules},
+    RuleStatsTracker,
+};
 use tui::{
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::Widget,
+    widgets::{Clear, Paragraph, Widget, Wrap},
 };
 
 use crate::{
     components::{MovableList, MovableListItem, MovableListState},
     define_widget,
-    interactive::RuleSort,
-    AsColor,
+    interactive::RuleStatsSort,
+    ui::{
+        utils::{centered_rect, get_focused_block},
+        RuleMatchPrompt,
+    },
+    AsColor, HMS,
 };
 
 define_widget!(RulePage);
@@ -17,6 +26,93 @@ define_widget!(RulePage);
 impl<'a> Widget for RulePage<'a> {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
         MovableList::new("Rules", &self.state.rule_state).render(area, buf);
+        if let Some(ref prompt) = self.state.rule_match {
+            RuleMatchPopup {
+                prompt,
+                rules: &self.state.rules,
+            }
+            .render(area, buf);
+        }
+    }
+}
+
+struct RuleMatchPopup<'a> {
+    prompt: &'a RuleMatchPrompt,
+    rules: &'a Rules,
+}
+
+impl<'a> Widget for RuleMatchPopup<'a> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let label = Style::default().fg(Color::DarkGray);
+        let line = |name: &'static str, style: Style, index: usize, rule: &Rule| {
+            Spans(vec![
+                Span::styled(format!("{:<11}", name), style),
+                Span::styled(format!("#{:<5}", index), label),
+                Span::styled(
+                    format!("{:<16}", <&'static str>::from(rule.rule_type)),
+                    Style::default().fg(rule.rule_type.as_color()),
+                ),
+                Span::raw(format!("{} → ", rule.payload)),
+                Span::styled(rule.proxy.to_owned(), Style::default().fg(Color::Yellow)),
+            ])
+        };
+
+        let mut text = vec![Spans(vec![
+            Span::styled(format!("{:<11}", "Target"), label),
+            Span::raw(if self.prompt.is_editing() {
+                format!("{}▏", self.prompt.input)
+            } else {
+                self.prompt.input.to_owned()
+            }),
+        ])];
+        match self.prompt.target {
+            None => {
+                text.push(Spans::default());
+                text.push(Spans::from(Span::styled(
+                    "Domain or IP, with optional port. [Enter] Match [Esc] Cancel",
+                    label,
+                )));
+            }
+            Some(ref target) => {
+                let result = self.rules.find_match(target);
+                text.push(Spans::default());
+                for unknown in &result.unknown {
+                    let mut spans = line(
+                        "May match",
+                        Style::default().fg(Color::Yellow),
+                        unknown.rule.index,
+                        unknown.rule.rule,
+                    );
+                    spans
+                        .0
+                        .push(Span::styled(format!(" ({})", unknown.reason), label));
+                    text.push(spans);
+                }
+                text.push(match result.matched {
+                    Some(matched) => line(
+                        "Matched",
+                        Style::default().fg(Color::Green),
+                        matched.index,
+                        matched.rule,
+                    ),
+                    None => Spans::from(Span::styled(
+                        "No rule matched",
+                        Style::default().fg(Color::Red),
+                    )),
+                });
+            }
+        }
+
+        let area = centered_rect(
+            area.width.saturating_sub(4).min(100),
+            area.height.saturating_sub(2).min(text.len() as u16 + 2),
+            area,
+        );
+        Clear.render(area, buf);
+        Paragraph::new(text)
+            .block(get_focused_block("Match rule"))
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
     }
 }
 
@@ -40,9 +136,12 @@ impl AsColor for RuleType {
     }
 }
 
-impl<'a> From<Rules> for MovableListState<'a, Rule, RuleSort> {
+impl<'a> From<Rules> for MovableListState<'a, RuleWithStats, RuleStatsSort> {
     fn from(val: Rules) -> Self {
-        Self::new_with_sort(val.rules, RuleSort::default())
+        Self::new_with_sort(
+            RuleStatsTracker::default().with_stats(&val),
+            RuleStatsSort::default(),
+        )
     }
 }
 
@@ -72,3 +171,37 @@ impl<'a> MovableListItem<'a> for Rule {
         .into()
     }
 }
+
+impl<'a> MovableListItem<'a> for RuleWithStats {
+    fn to_spans(&self) -> Spans<'a> {
+        let mut spans = self.rule.to_spans();
+        let gray = Style::default().fg(Color::DarkGray);
+        let stats = &self.stats;
+        spans.0.push(Span::raw(" ".repeat(
+            20_usize.saturating_sub(self.rule.proxy.chars().count()) + 2,
+        )));
+        if stats.hits == 0 {
+            spans.0.push(Span::styled("No hit", gray));
+            return spans;
+        }
+        spans.0.extend([
+            Span::styled(
+                format!("{:>5} ", stats.hits),
+                Style::default().fg(Color::Green),
+            ),
+            Span::styled("hits ", gray),
+            Span::raw(format!(
+                "{:>10} ",
+                ByteSize(stats.traffic).to_string_as(true)
+            )),
+            Span::styled(
+                stats
+                    .last_hit
+                    .map(|time| format!("{} ago", (Utc::now() - time).hms()))
+                    .unwrap_or_default(),
+                gray,
+            ),
+        ]);
+        spans
+    }
+}
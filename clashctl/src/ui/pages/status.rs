@@ -1,4 +1,4 @@
-use std::iter::repeat;
+use std::iter::repeat_n;
 
 use bytesize::ByteSize;
 use tui::{
@@ -47,8 +47,10 @@ impl<'a> Widget for StatusPage<'a> {
             }
         };
 
-        let con_num = self.state.con_state.len().to_string();
+        let (opened, closed) = self.state.con_churn;
+        let con_num = format!("(+{} -{}) {}", opened, closed, self.state.con_state.len());
         let (total_up, total_down) = self.state.con_size;
+        let (con_up, con_down) = self.state.con_speed;
         let height = main[0].height;
         let clash_ver = self
             .state
@@ -81,17 +83,19 @@ impl<'a> Widget for StatusPage<'a> {
                 "▼ Max",
                 &(ByteSize(self.state.max_traffic.down).to_string_as(true) + "/s"),
             ),
+            ("▲ Conns.", &(ByteSize(con_up).to_string_as(true) + "/s")),
+            ("▼ Conns.", &(ByteSize(con_down).to_string_as(true) + "/s")),
             ("▲ Total", &ByteSize(total_up).to_string_as(true)),
             ("▼ Total", &ByteSize(total_down).to_string_as(true)),
         ];
 
         let info_str = info
             .into_iter()
-            .chain(
-                repeat(("", ""))
-                    .take((height as usize).saturating_sub(info.len() + tails.len() + 2)),
-            )
-            .chain(tails.into_iter())
+            .chain(repeat_n(
+                ("", ""),
+                (height as usize).saturating_sub(info.len() + tails.len() + 2),
+            ))
+            .chain(tails)
             .map(|(title, content)| format!(" {:<13}{:>18} ", title, content))
             .fold(String::with_capacity((30 * height).into()), |mut a, b| {
                 a.push_str(&b);
@@ -4,9 +4,9 @@ use std::{
     time::Duration,
 };
 
-use clashctl_core::Clash;
+use clashctl_core::{Backoff, ClashApi, ConnectionTracker, LongHaulTransport, StreamEvent};
 use crossterm::event::Event as CrossTermEvent;
-use log::warn;
+use log::{info, warn};
 use rayon::prelude::*;
 
 use crate::{
@@ -32,18 +32,27 @@ pub fn servo(tx: Sender<Event>, rx: Receiver<Action>, opt: TuiOpt, flags: Flags)
         let handle2 = r.spawn(|| traffic_job(tx_clone, &clash));
 
         let tx_clone = tx.clone();
-        let handle3 = r.spawn(|| log_job(tx_clone, &clash));
+        let handle3 = r.spawn(|| log_job(&opt, tx_clone, &clash));
 
         let tx_clone = tx.clone();
         let handle4 = r.spawn(|| req_job(&opt, &flags, tx_clone, &clash));
 
-        let handle5 = r.spawn(|| action_job(&opt, &flags, tx, rx, &clash));
+        let tx_clone = tx.clone();
+        let handle5 = r.spawn(|| action_job(&opt, &flags, tx_clone, rx, &clash));
+
+        // Connections can only be streamed through WebSocket, otherwise they are
+        // polled in `req_job`
+        let handle6 = r.spawn(|| match clash.transport() {
+            LongHaulTransport::WebSocket => connection_job(tx, &clash),
+            LongHaulTransport::Chunked => Ok(()),
+        });
 
         handle1.join().unwrap()?;
         handle2.join().unwrap()?;
         handle3.join().unwrap()?;
         handle4.join().unwrap()?;
         handle5.join().unwrap()?;
+        handle6.join().unwrap()?;
 
         Ok(())
     })
@@ -52,7 +61,7 @@ pub fn servo(tx: Sender<Event>, rx: Receiver<Action>, opt: TuiOpt, flags: Flags)
 fn input_job(tx: Sender<Event>) -> TuiResult<()> {
     loop {
         match crossterm::event::read() {
-            Ok(CrossTermEvent::Key(event)) => tx.send(Event::from(event))?,
+            Ok(CrossTermEvent::Key(event)) => tx.send(Event::Key(event))?,
             Err(_) => {
                 tx.send(Event::Quit)?;
                 break;
@@ -63,64 +72,98 @@ fn input_job(tx: Sender<Event>) -> TuiResult<()> {
     Ok(())
 }
 
-fn req_job(_opt: &TuiOpt, _flags: &Flags, tx: Sender<Event>, clash: &Clash) -> TuiResult<()> {
+fn req_job<C: ClashApi>(
+    _opt: &TuiOpt,
+    _flags: &Flags,
+    tx: Sender<Event>,
+    clash: &C,
+) -> TuiResult<()> {
     let mut interval = Interval::every(Duration::from_millis(50));
     let mut connection_pulse = Pulse::new(20); // Every 1 s
     let mut proxies_pulse = Pulse::new(100); //   Every 5 s + 0 tick
     let mut rules_pulse = Pulse::new(101); //     Every 5 s + 1 tick
     let mut version_pulse = Pulse::new(102); //   Every 5 s + 2 tick
     let mut config_pulse = Pulse::new(103); //    Every 5 s + 3 tick
+    let mut providers_pulse = Pulse::new(104); // Every 5 s + 4 tick
+    let poll_connections = clash.transport() == LongHaulTransport::Chunked;
+    let mut tracker = ConnectionTracker::new();
+
+    let send = |update| send_update(&tx, update);
 
     loop {
         if version_pulse.tick() {
-            tx.send(Event::Update(UpdateEvent::Version(clash.get_version()?)))?;
+            send(clash.get_version().map(UpdateEvent::Version))?;
         }
-        if connection_pulse.tick() {
-            tx.send(Event::Update(UpdateEvent::Connection(
-                clash.get_connections()?.into(),
-            )))?;
+        if connection_pulse.tick() && poll_connections {
+            send(
+                clash
+                    .get_connections()
+                    .map(|x| UpdateEvent::Connection(tracker.track(x))),
+            )?;
         }
         if rules_pulse.tick() {
-            tx.send(Event::Update(UpdateEvent::Rules(clash.get_rules()?)))?;
+            send(clash.get_rules().map(UpdateEvent::Rules))?;
         }
         if proxies_pulse.tick() {
-            tx.send(Event::Update(UpdateEvent::Proxies(clash.get_proxies()?)))?;
+            send(clash.get_proxies().map(UpdateEvent::Proxies))?;
         }
         if config_pulse.tick() {
-            tx.send(Event::Update(UpdateEvent::Config(clash.get_configs()?)))?;
+            send(clash.get_configs().map(UpdateEvent::Config))?;
+        }
+        if providers_pulse.tick() {
+            send(clash.get_providers().map(UpdateEvent::Providers))?;
         }
         interval.tick();
     }
 }
 
-fn traffic_job(tx: Sender<Event>, clash: &Clash) -> TuiResult<()> {
-    let mut traffics = clash.get_traffic()?;
-    loop {
-        match traffics.next() {
-            Some(Ok(traffic)) => tx.send(Event::Update(UpdateEvent::Traffic(traffic)))?,
-            Some(Err(e)) => warn!("{:?}", e),
-            None => warn!("No more traffic"),
-        }
+/// Send `update` if the request succeeded. Requests may fail while clash is
+/// restarting, so only warn and wait for the next refresh instead of quitting
+fn send_update(tx: &Sender<Event>, update: clashctl_core::Result<UpdateEvent>) -> TuiResult<()> {
+    match update {
+        Ok(update) => tx.send(Event::Update(update))?,
+        Err(e) => warn!("{}", e),
     }
+    Ok(())
 }
 
-fn log_job(tx: Sender<Event>, clash: &Clash) -> TuiResult<()> {
-    loop {
-        let mut logs = clash.get_log()?;
-        match logs.next() {
-            Some(Ok(log)) => tx.send(Event::Update(UpdateEvent::Log(log)))?,
-            Some(Err(e)) => warn!("{:?}", e),
-            None => warn!("No more traffic"),
-        }
+fn traffic_job<C: ClashApi>(tx: Sender<Event>, clash: &C) -> TuiResult<()> {
+    for event in clash.get_traffic_reconnect(Backoff::default()) {
+        tx.send(Event::Update(match event {
+            StreamEvent::Data(traffic) => UpdateEvent::Traffic(traffic),
+            StreamEvent::State(state) => UpdateEvent::TrafficStream(state),
+        }))?
+    }
+    Ok(())
+}
+
+fn log_job<C: ClashApi>(opt: &TuiOpt, tx: Sender<Event>, clash: &C) -> TuiResult<()> {
+    for event in clash.get_log_reconnect(opt.log_level.clone(), Backoff::default()) {
+        tx.send(Event::Update(match event {
+            StreamEvent::Data(log) => UpdateEvent::Log(log),
+            StreamEvent::State(state) => UpdateEvent::LogStream(state),
+        }))?
     }
+    Ok(())
+}
+
+fn connection_job<C: ClashApi>(tx: Sender<Event>, clash: &C) -> TuiResult<()> {
+    let mut tracker = ConnectionTracker::new();
+    for event in clash.get_connections_reconnect(Backoff::default())? {
+        tx.send(Event::Update(match event {
+            StreamEvent::Data(connections) => UpdateEvent::Connection(tracker.track(connections)),
+            StreamEvent::State(state) => UpdateEvent::ConnectionStream(state),
+        }))?
+    }
+    Ok(())
 }
 
-fn action_job(
+fn action_job<C: ClashApi>(
     _opt: &TuiOpt,
     flags: &Flags,
     tx: Sender<Event>,
     rx: Receiver<Action>,
-    clash: &Clash,
+    clash: &C,
 ) -> TuiResult<()> {
     while let Ok(action) = rx.recv() {
         tx.send(Event::Action(action.clone()))?;
@@ -130,7 +173,7 @@ fn action_job(
                     .par_iter()
                     .filter_map(|proxy| {
                         clash
-                            .get_proxy_delay(proxy, flags.test_url.as_str(), flags.timeout)
+                            .get_proxy_delay(proxy, flags.test_url().as_str(), flags.timeout())
                             .err()
                     })
                     .collect::<Vec<_>>();
@@ -158,7 +201,155 @@ fn action_job(
                     .map_err(|e| warn!("{:?}", e));
                 tx.send(Event::Update(UpdateEvent::Proxies(clash.get_proxies()?)))?;
             }
+            Action::CloseConnections { ids } => {
+                let closed = ids
+                    .into_iter()
+                    .filter(|id| match clash.close_one_connection(id) {
+                        Ok(_) => true,
+                        Err(e) => {
+                            warn!("Failed to close {}: {}", id, e);
+                            false
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                info!("Closed {} connection(s)", closed.len());
+                tx.send(Event::Update(UpdateEvent::ConnectionsClosed(closed)))?;
+            }
+            Action::CloseAllConnections => {
+                let ids = clash
+                    .get_connections()
+                    .map(|x| x.connections.into_iter().map(|con| con.id).collect())
+                    .unwrap_or_default();
+                match clash.close_connections() {
+                    Ok(_) => {
+                        info!("Closed all connections");
+                        tx.send(Event::Update(UpdateEvent::ConnectionsClosed(ids)))?;
+                    }
+                    Err(e) => warn!("Failed to close all connections: {}", e),
+                }
+            }
+            Action::PatchConfig(patch) => {
+                let error = clash.patch_configs(&patch).err().map(|e| {
+                    warn!("Failed to update configs: {}", e);
+                    e.to_string()
+                });
+                // Refresh first, as the patch is checked against the refreshed configs
+                send_update(&tx, clash.get_configs().map(UpdateEvent::Config))?;
+                tx.send(Event::Update(UpdateEvent::ConfigPatched { patch, error }))?;
+            }
+            Action::UpdateProvider { name } => {
+                match clash.update_provider(&name) {
+                    Ok(_) => info!("Updated provider {}", name),
+                    Err(e) => warn!("Failed to update provider {}: {}", name, e),
+                }
+                send_update(&tx, clash.get_providers().map(UpdateEvent::Providers))?;
+                send_update(&tx, clash.get_proxies().map(UpdateEvent::Proxies))?;
+            }
+            Action::HealthcheckProvider { name } => {
+                match clash.healthcheck_provider(&name) {
+                    Ok(_) => info!("Health checked provider {}", name),
+                    Err(e) => warn!("Failed to check provider {}: {}", name, e),
+                }
+                send_update(&tx, clash.get_providers().map(UpdateEvent::Providers))?;
+                send_update(&tx, clash.get_proxies().map(UpdateEvent::Proxies))?;
+            }
         }
     }
     Ok(())
 }
+
+#[test]
+fn test_action_job() {
+    use std::{env, sync::mpsc::channel};
+
+    use clashctl_core::{mock::FakeClash, model::Mode};
+
+    use crate::{
+        interactive::Config,
+        ui::{init_config, ConfigField, FieldStatus, TuiStates},
+    };
+
+    let clash = FakeClash::new();
+    let (event_tx, event_rx) = channel();
+    let (action_tx, action_rx) = channel();
+    let actions = [
+        Action::PatchConfig(Mode::Global.into()),
+        Action::ApplySelection {
+            group: "Proxy".to_owned(),
+            proxy: "US-01".to_owned(),
+        },
+        Action::CloseConnections {
+            ids: vec!["1".to_owned()],
+        },
+        Action::HealthcheckProvider {
+            name: "Subscription".to_owned(),
+        },
+    ];
+    actions
+        .into_iter()
+        .for_each(|action| action_tx.send(action).unwrap());
+    // Job quits once all actions are handled
+    drop(action_tx);
+    action_job(
+        &TuiOpt::default(),
+        &Flags::default(),
+        event_tx,
+        action_rx,
+        &clash,
+    )
+    .unwrap();
+
+    let state = clash.state();
+    assert_eq!(state.configs.mode, Mode::Global);
+    assert_eq!(state.proxies["Proxy"].now.as_deref(), Some("US-01"));
+    assert_eq!(state.connections.connections.len(), 1);
+    assert!(clash.calls().contains(&"healthcheck_provider"));
+
+    init_config(Config::from_dir(env::temp_dir().join("clashctl-test-servo.ron")).unwrap());
+    let mut tui = TuiStates::default();
+    for event in event_rx.try_iter() {
+        tui.handle(event).unwrap();
+    }
+    assert_eq!(
+        tui.config_state.status(ConfigField::Mode),
+        Some(&FieldStatus::Applied)
+    );
+    assert_eq!(
+        tui.provider_state
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>(),
+        ["Subscription", "default"]
+    );
+
+    // No failure quits the job, and the patch is still reported
+    clash.fail("patch_configs", 400);
+    clash.fail("get_configs", 500);
+    clash.fail("get_providers", 500);
+    let (event_tx, event_rx) = channel();
+    let (action_tx, action_rx) = channel();
+    action_tx
+        .send(Action::PatchConfig(Mode::Direct.into()))
+        .unwrap();
+    action_tx
+        .send(Action::UpdateProvider {
+            name: "Subscription".to_owned(),
+        })
+        .unwrap();
+    drop(action_tx);
+    action_job(
+        &TuiOpt::default(),
+        &Flags::default(),
+        event_tx,
+        action_rx,
+        &clash,
+    )
+    .unwrap();
+    for event in event_rx.try_iter() {
+        tui.handle(event).unwrap();
+    }
+    assert!(matches!(
+        tui.config_state.status(ConfigField::Mode),
+        Some(FieldStatus::Failed(_))
+    ));
+}
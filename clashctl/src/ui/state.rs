@@ -1,22 +1,116 @@
 use std::{collections::HashMap, time::Instant};
 
-use clashctl_core::model::{ConnectionWithSpeed, Log, Rule, Traffic, Version};
+use clashctl_core::{
+    model::{ConnectionWithSpeed, Log, Provider, RuleWithStats, Rules, Traffic, Version},
+    MatchTarget, RuleStatsTracker, StreamState,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use log::warn;
 use smart_default::SmartDefault;
 
 use crate::{
-    interactive::{Noop, RuleSort},
+    interactive::{ConSort, Noop, RuleStatsSort},
     ui::{
         components::{MovableListManage, MovableListManager, MovableListState, ProxyTree},
-        TuiResult,
+        get_config, get_config_mut, TuiResult,
     },
-    Action, ConfigState, Event, InputEvent, UpdateEvent,
+    Action, ConfigField, ConfigState, EditEvent, Event, FilterEvent, InputEvent, ListEvent,
+    UpdateEvent,
 };
 
 pub(crate) type LogListState<'a> = MovableListState<'a, Log, Noop>;
-pub(crate) type ConListState<'a> = MovableListState<'a, ConnectionWithSpeed, Noop>;
-pub(crate) type RuleListState<'a> = MovableListState<'a, Rule, RuleSort>;
+pub(crate) type ConListState<'a> = MovableListState<'a, ConnectionWithSpeed, ConSort>;
+pub(crate) type RuleListState<'a> = MovableListState<'a, RuleWithStats, RuleStatsSort>;
+pub(crate) type ProviderListState<'a> = MovableListState<'a, Provider, Noop>;
 pub(crate) type DebugListState<'a> = MovableListState<'a, Event, Noop>;
 
+/// Connection shown in the detail popup of Conns page
+#[derive(Debug, Clone)]
+pub struct ConnectionDetail {
+    pub connection: ConnectionWithSpeed,
+    /// (upload, download) speed of each update, latest last
+    pub speeds: Vec<(u64, u64)>,
+    /// Whether the connection has been closed since the popup is opened
+    pub closed: bool,
+}
+
+impl ConnectionDetail {
+    const MAX_SPEEDS: usize = 300;
+
+    pub fn new(connection: ConnectionWithSpeed) -> Self {
+        let mut ret = Self {
+            connection,
+            speeds: vec![],
+            closed: false,
+        };
+        ret.push_speed();
+        ret
+    }
+
+    pub fn id(&self) -> &str {
+        &self.connection.connection.id
+    }
+
+    /// Update with latest connections, or mark as closed if it's gone
+    pub fn update(&mut self, connections: &[ConnectionWithSpeed]) {
+        match connections.iter().find(|x| x.connection.id == self.id()) {
+            Some(con) => {
+                self.connection = con.to_owned();
+                self.push_speed();
+            }
+            None => self.closed = true,
+        }
+    }
+
+    fn push_speed(&mut self) {
+        if self.speeds.len() >= Self::MAX_SPEEDS {
+            self.speeds.remove(0);
+        }
+        self.speeds.push((
+            self.connection.upload.unwrap_or_default(),
+            self.connection.download.unwrap_or_default(),
+        ));
+    }
+}
+
+/// Prompt on Rules page to find which rule a host or IP would match
+#[derive(Debug, Clone, Default)]
+pub struct RuleMatchPrompt {
+    pub input: String,
+    /// Parsed target, set once input is confirmed
+    pub target: Option<MatchTarget>,
+}
+
+impl RuleMatchPrompt {
+    pub fn is_editing(&self) -> bool {
+        self.target.is_none()
+    }
+
+    /// Returns whether the prompt should be kept
+    fn handle(&mut self, event: EditEvent) -> bool {
+        match event {
+            EditEvent::Push(char) => self.input.push(char),
+            EditEvent::Pop => {
+                self.input.pop();
+            }
+            EditEvent::Confirm => {
+                if !self.input.trim().is_empty() {
+                    self.target = Some(MatchTarget::parse(&self.input))
+                }
+            }
+            EditEvent::Cancel => return false,
+        }
+        true
+    }
+}
+
+/// An action waiting for user to confirm
+#[derive(Debug, Clone)]
+pub struct PendingAction {
+    pub prompt: String,
+    pub action: Action,
+}
+
 #[derive(Debug, Clone, SmartDefault)]
 pub struct TuiStates<'a> {
     pub should_quit: bool,
@@ -30,8 +124,23 @@ pub struct TuiStates<'a> {
     pub show_debug: bool,
     pub proxy_tree: ProxyTree<'a>,
     pub rule_freq: HashMap<String, usize>,
+    /// Rules in their original order, for matching
+    pub rules: Rules,
+    pub rule_match: Option<RuleMatchPrompt>,
+    pub rule_stats: RuleStatsTracker,
     // (upload_size, download_size)
     pub con_size: (u64, u64),
+    // (upload_speed, download_speed), summed over all connections
+    pub con_speed: (u64, u64),
+    // (opened, closed) since last update
+    pub con_churn: (usize, usize),
+    pub traffic_stream: Option<StreamState>,
+    pub log_stream: Option<StreamState>,
+    pub connection_stream: Option<StreamState>,
+    pub pending_action: Option<PendingAction>,
+    pub con_detail: Option<ConnectionDetail>,
+    /// Name of provider whose proxies are shown in the popup of Providers page
+    pub provider_detail: Option<String>,
 
     #[default(_code = "{
         let mut ret = MovableListState::default();
@@ -39,8 +148,19 @@ pub struct TuiStates<'a> {
         ret
     }")]
     pub log_state: LogListState<'a>,
+    #[default(_code = "{
+        let mut ret = ConListState::new_with_sort(vec![], get_config().sort.connections.clone());
+        ret.selectable();
+        ret
+    }")]
     pub con_state: ConListState<'a>,
     pub rule_state: RuleListState<'a>,
+    #[default(_code = "{
+        let mut ret = ProviderListState::default();
+        ret.selectable();
+        ret
+    }")]
+    pub provider_state: ProviderListState<'a>,
     pub debug_state: DebugListState<'a>,
     pub config_state: ConfigState,
 }
@@ -48,10 +168,21 @@ pub struct TuiStates<'a> {
 // TODO fix: drop_events not working
 impl<'a> TuiStates<'a> {
     pub const TITLES: &'static [&'static str] = &[
-        "Status", "Proxies", "Rules", "Conns", "Logs", "Configs", "Debug",
+        "Status",
+        "Proxies",
+        "Rules",
+        "Conns",
+        "Logs",
+        "Configs",
+        "Providers",
+        "Debug",
     ];
 
     pub fn handle(&mut self, event: Event) -> TuiResult<Option<Action>> {
+        let event = match event {
+            Event::Key(key) => self.map_key(key),
+            event => event,
+        };
         self.all_events_recv += 1;
         if self.debug_state.len() >= 300 {
             let _ = self.drop_events(100);
@@ -69,6 +200,32 @@ impl<'a> TuiStates<'a> {
         }
     }
 
+    /// While a filter is being edited, typed keys go into its query instead of
+    /// triggering shortcuts
+    fn map_key(&mut self, key: KeyEvent) -> Event {
+        if self.pending_action.is_some() {
+            return Event::from_confirming(key);
+        }
+        let rule_match = self
+            .rule_match
+            .as_ref()
+            .is_some_and(RuleMatchPrompt::is_editing);
+        if (self.title() == "Configs" && self.config_state.is_editing())
+            || (self.title() == "Rules" && rule_match)
+        {
+            if let Some(event) = EditEvent::from_editing(key) {
+                return Event::Input(InputEvent::Edit(event));
+            }
+        }
+        let editing = self
+            .active_list()
+            .is_some_and(|list| list.list_filter().is_editing());
+        match FilterEvent::from_editing(key) {
+            Some(event) if editing => Event::Input(InputEvent::Filter(event)),
+            _ => key.into(),
+        }
+    }
+
     #[inline]
     pub fn page_len(&mut self) -> usize {
         if self.show_debug {
@@ -90,6 +247,7 @@ impl<'a> TuiStates<'a> {
             "Logs" => Some(MovableListManager::Log(&mut self.log_state)),
             "Conns" => Some(MovableListManager::Connection(&mut self.con_state)),
             "Proxies" => Some(MovableListManager::Proxy(&mut self.proxy_tree)),
+            "Providers" => Some(MovableListManager::Provider(&mut self.provider_state)),
             _ => None,
         }
     }
@@ -97,8 +255,21 @@ impl<'a> TuiStates<'a> {
     fn handle_update(&mut self, update: UpdateEvent) -> TuiResult<Option<Action>> {
         match update {
             UpdateEvent::Config(config) => self.config_state.update_clash(config),
-            UpdateEvent::Connection(connection) => {
+            UpdateEvent::Connection(tracked) => {
+                let connection = tracked.connections;
                 self.con_size = (connection.upload_total, connection.download_total);
+                self.con_speed = (
+                    tracked.upload_speed.unwrap_or_default(),
+                    tracked.download_speed.unwrap_or_default(),
+                );
+                self.con_churn = (tracked.opened.len(), tracked.closed.len());
+                if let Some(ref mut detail) = self.con_detail {
+                    detail.update(&connection.connections);
+                }
+                self.rule_stats
+                    .track(connection.connections.iter().map(|x| &x.connection));
+                self.rule_state
+                    .sorted_merge(self.rule_stats.with_stats(&self.rules));
                 self.con_state.sorted_merge(connection.connections);
                 self.con_state.with_index();
             }
@@ -114,11 +285,29 @@ impl<'a> TuiStates<'a> {
                 new_tree.sort_groups_with_frequency(&self.rule_freq);
                 self.proxy_tree.replace_with(new_tree);
             }
+            UpdateEvent::Providers(providers) => {
+                let mut providers = providers.providers.into_values().collect::<Vec<_>>();
+                providers.sort_by(|a, b| a.name.cmp(&b.name));
+                self.provider_state.sorted_merge(providers);
+            }
             UpdateEvent::Log(log) => self.log_state.push(log),
+            UpdateEvent::TrafficStream(state) => self.traffic_stream = Some(state),
+            UpdateEvent::LogStream(state) => self.log_stream = Some(state),
+            UpdateEvent::ConnectionStream(state) => self.connection_stream = Some(state),
+            UpdateEvent::ConnectionsClosed(ids) => {
+                if let Some(ref mut detail) = self.con_detail {
+                    detail.closed |= ids.iter().any(|id| id == detail.id());
+                }
+                self.con_state
+                    .retain(|con| !ids.contains(&con.connection.id))
+            }
             UpdateEvent::Rules(rules) => {
                 self.rule_freq = rules.owned_frequency();
-                self.rule_state.sorted_merge(rules.rules);
+                self.rule_state
+                    .sorted_merge(self.rule_stats.with_stats(&rules));
+                self.rules = rules;
             }
+            UpdateEvent::ConfigPatched { patch, error } => self.config_state.patched(&patch, error),
             UpdateEvent::ProxyTestLatencyDone => {
                 self.proxy_tree.end_testing();
             }
@@ -130,7 +319,10 @@ impl<'a> TuiStates<'a> {
         match event {
             InputEvent::TabGoto(index) => {
                 if index >= 1 && index <= self.page_len() as u8 {
-                    self.page_index = index - 1
+                    self.page_index = index - 1;
+                    self.con_detail = None;
+                    self.rule_match = None;
+                    self.provider_detail = None;
                 }
             }
             InputEvent::ToggleDebug => {
@@ -143,8 +335,18 @@ impl<'a> TuiStates<'a> {
                 }
             }
             InputEvent::Esc => {
+                if self.con_detail.take().is_some()
+                    || self.rule_match.take().is_some()
+                    || self.provider_detail.take().is_some()
+                {
+                    return Ok(None);
+                }
                 if let Some(mut list) = self.active_list() {
-                    list.end();
+                    if list.list_filter().is_active() {
+                        list.handle_filter(FilterEvent::Clear);
+                    } else {
+                        list.end();
+                    }
                 }
             }
             InputEvent::ToggleHold => {
@@ -152,11 +354,58 @@ impl<'a> TuiStates<'a> {
                     list.toggle();
                 }
             }
+            InputEvent::List(ListEvent {
+                code: KeyCode::Enter,
+                ..
+            }) if self.title() == "Conns" => {
+                if let Some(con) = self.con_state.selected() {
+                    self.con_detail = Some(ConnectionDetail::new(con.to_owned()));
+                }
+            }
+            InputEvent::List(ListEvent {
+                code: KeyCode::Enter,
+                ..
+            }) if self.title() == "Providers" => {
+                if let Some(provider) = self.provider_state.selected() {
+                    self.provider_detail = Some(provider.name.to_owned());
+                }
+            }
+            InputEvent::List(ListEvent { code, .. }) if self.title() == "Configs" => match code {
+                KeyCode::Up => self.config_state.select(false),
+                KeyCode::Down => self.config_state.select(true),
+                KeyCode::Enter => {
+                    let field = self.config_state.selected();
+                    return Ok(self.config_state.edit(field).map(Action::PatchConfig));
+                }
+                _ => {}
+            },
             InputEvent::List(list_event) => {
                 if let Some(mut list) = self.active_list() {
                     return Ok(list.handle(list_event));
                 }
             }
+            InputEvent::TestLatency if self.title() == "Providers" => {
+                return Ok(self
+                    .selected_provider()
+                    .map(|x| Action::HealthcheckProvider {
+                        name: x.name.to_owned(),
+                    }));
+            }
+            InputEvent::UpdateProvider if self.title() == "Providers" => {
+                return Ok(match self.selected_provider() {
+                    Some(x) if x.vehicle_type.is_updatable() => Some(Action::UpdateProvider {
+                        name: x.name.to_owned(),
+                    }),
+                    Some(x) => {
+                        warn!(
+                            "Provider {} is {}, which cannot be updated",
+                            x.name, x.vehicle_type
+                        );
+                        None
+                    }
+                    None => None,
+                });
+            }
             InputEvent::TestLatency => {
                 if self.title() == "Proxies" && !self.proxy_tree.is_testing() {
                     self.proxy_tree.start_testing();
@@ -174,17 +423,128 @@ impl<'a> TuiStates<'a> {
                 if let Some(mut list) = self.active_list() {
                     list.next_sort();
                 }
+                self.save_sort();
             }
             InputEvent::PrevSort => {
                 if let Some(mut list) = self.active_list() {
                     list.prev_sort();
                 }
+                self.save_sort();
             }
-            InputEvent::Other(_) => {} // InterfaceEvent::Other(event) => self.handle_list(event),
+            InputEvent::Filter(event) => {
+                if let Some(mut list) = self.active_list() {
+                    list.handle_filter(event);
+                }
+            }
+            InputEvent::CloseConnection if self.title() == "Conns" => {
+                if let Some(con) = self.con_state.selected() {
+                    let meta = &con.connection.metadata;
+                    self.pending_action = Some(PendingAction {
+                        prompt: format!(
+                            "Close connection to {}:{}?",
+                            meta.host, meta.destination_port
+                        ),
+                        action: Action::CloseConnections {
+                            ids: vec![con.connection.id.to_owned()],
+                        },
+                    });
+                }
+            }
+            InputEvent::CloseConnections if self.title() == "Conns" => {
+                let filter = self.con_state.list_filter();
+                let ids = self
+                    .con_state
+                    .visible()
+                    .map(|con| con.connection.id.to_owned())
+                    .collect::<Vec<_>>();
+                if !ids.is_empty() {
+                    self.pending_action = Some(if filter.is_active() {
+                        PendingAction {
+                            prompt: format!(
+                                "Close {} connection(s) matching \"{}\"?",
+                                ids.len(),
+                                filter.query()
+                            ),
+                            action: Action::CloseConnections { ids },
+                        }
+                    } else {
+                        PendingAction {
+                            prompt: format!("Close all {} connection(s)?", ids.len()),
+                            action: Action::CloseAllConnections,
+                        }
+                    });
+                }
+            }
+            InputEvent::CycleMode if self.title() == "Configs" => {
+                return Ok(self
+                    .config_state
+                    .edit(ConfigField::Mode)
+                    .map(Action::PatchConfig));
+            }
+            InputEvent::MatchRule if self.title() == "Rules" => {
+                self.rule_match = Some(RuleMatchPrompt::default())
+            }
+            InputEvent::Edit(event) if self.title() == "Rules" => {
+                if let Some(ref mut prompt) = self.rule_match {
+                    if !prompt.handle(event) {
+                        self.rule_match = None
+                    }
+                }
+            }
+            InputEvent::Edit(event) => {
+                return Ok(self
+                    .config_state
+                    .handle_edit(event)
+                    .map(Action::PatchConfig));
+            }
+            InputEvent::Confirm => {
+                return Ok(self.pending_action.take().map(|pending| pending.action));
+            }
+            InputEvent::Cancel => self.pending_action = None,
+            InputEvent::CloseConnection
+            | InputEvent::CloseConnections
+            | InputEvent::CycleMode
+            | InputEvent::MatchRule
+            | InputEvent::UpdateProvider
+            | InputEvent::Other(_) => {} // InterfaceEvent::Other(event) => self.handle_list(event),
         }
         Ok(None)
     }
 
+    /// Provider shown in the popup, or on top of the held list
+    pub fn selected_provider(&self) -> Option<&Provider> {
+        match self.provider_detail {
+            Some(ref name) => self.provider_state.iter().find(|x| &x.name == name),
+            None => self.provider_state.selected(),
+        }
+    }
+
+    /// Persist sort of current page into config, if it's configurable. Failing
+    /// to write config should not quit the TUI, so only warn
+    fn save_sort(&self) {
+        if self.title() == "Conns" {
+            let mut config = get_config_mut();
+            config.sort.connections = self.con_state.current_sort().clone();
+            if let Err(e) = config.write() {
+                warn!("Failed to save sort: {}", e);
+            }
+        }
+    }
+
+    /// The first stream that is not connected, if any
+    pub fn disconnected_stream(&self) -> Option<(&str, &StreamState)> {
+        [
+            ("Traffic", &self.traffic_stream),
+            ("Log", &self.log_stream),
+            ("Connection", &self.connection_stream),
+        ]
+        .into_iter()
+        .find_map(|(name, state)| match state {
+            Some(state) if !state.is_connected() => Some((name, state)),
+            _ => None,
+        })
+    }
+
     pub const fn debug_page_index(&self) -> u8 {
         Self::TITLES.len() as u8 - 1
     }
@@ -1,3 +1,4 @@
+use clashctl_core::{model::Level, strum::VariantNames};
 use smart_default::SmartDefault;
 
 #[derive(Debug, SmartDefault, clap::Parser)]
@@ -6,4 +7,8 @@ pub struct TuiOpt {
     #[default = 5.0]
     /// Interval between requests
     pub interval: f32,
+
+    #[clap(long, ignore_case = true, possible_values = Level::VARIANTS)]
+    /// Only show logs at or above this level. Default to log level of clash
+    pub log_level: Option<Level>,
 }
@@ -1,6 +1,7 @@
 use std::{borrow::Cow, ops::Range};
 
 use tui::{
+    layout::Rect,
     style::{Color, Modifier, Style},
     text::{Span, Spans},
     widgets::{Block, Borders},
@@ -8,7 +9,7 @@ use tui::{
 
 use crate::{IntoSpans, Wrap};
 
-pub fn help_footer(content: &str, normal: Style, highlight: Style) -> Spans {
+pub fn help_footer(content: &str, normal: Style, highlight: Style) -> Spans<'_> {
     if content.is_empty() {
         Spans(vec![])
     } else if content.len() == 1 {
@@ -25,7 +26,7 @@ pub fn help_footer(content: &str, normal: Style, highlight: Style) -> Spans {
     }
 }
 
-pub fn tagged_footer<T: ToString>(label: &str, style: Style, content: T) -> Spans {
+pub fn tagged_footer<T: ToString>(label: &str, style: Style, content: T) -> Spans<'_> {
     let mut ret = help_footer(label, style, style.add_modifier(Modifier::BOLD)).wrapped();
     ret.0.push(Span::styled(
         content.to_string().wrapped(),
@@ -98,14 +99,14 @@ pub fn spans_window_owned<'a>(mut spans: Spans<'a>, range: &Range<usize>) -> Spa
     }
 }
 
-pub fn get_block(title: &str) -> Block {
+pub fn get_block(title: &str) -> Block<'_> {
     Block::default()
         .borders(Borders::ALL)
         .style(Style::default().fg(Color::LightBlue))
         .title(Span::raw(format!(" {} ", title)))
 }
 
-pub fn get_focused_block(title: &str) -> Block {
+pub fn get_focused_block(title: &str) -> Block<'_> {
     Block::default()
         .borders(Borders::ALL)
         .title(Span::styled(
@@ -115,6 +116,17 @@ pub fn get_focused_block(title: &str) -> Block {
         .style(Style::default().fg(Color::Green))
 }
 
+/// Area of given size centered in `area`, shrunk to fit if needed
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let (width, height) = (width.min(area.width), height.min(area.height));
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
 pub fn get_text_style() -> Style {
     Style::default().fg(Color::White)
 }
@@ -18,6 +18,6 @@ impl Pulse {
 
     #[inline]
     pub fn is_pulse(&self) -> bool {
-        self.counter % self.pulse == 0
+        self.counter.is_multiple_of(self.pulse)
     }
 }
@@ -44,7 +44,7 @@ impl TicksCounter {
         // Ticks per Second
         Some(
             20_000.0
-                / ((self.inner.get(0)?
+                / ((self.inner.front()?
                     - self
                         .inner
                         .get(20)